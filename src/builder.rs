@@ -1,10 +1,14 @@
 use std::{
     collections::BTreeMap,
-    io::{Seek, SeekFrom, Write},
+    io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom, Write},
     marker::PhantomData,
+    mem,
 };
 
-use crate::{Error, Header, HeaderFlags, MaybeUnknown, Question, Resource, ResourceData, Type};
+use crate::{
+    Class, EdnsOption, EdnsOptionCode, EitherError, Error, Header, HeaderFlags, MaybeUnknown, NameVisitor, Packet, Question,
+    RCODE_BADVERS, RCode, Resource, ResourceData, SvcParam, SvcParamKey, Type,
+};
 
 pub struct WantsHeader;
 pub struct WantsQuestions;
@@ -12,6 +16,136 @@ pub struct WantsAnswers;
 pub struct WantsAuthorities;
 pub struct WantsAdditionals;
 
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CompressionStats {
+    pub compressed: usize,
+    pub beyond_pointer_range: usize,
+}
+
+// the compression table still lives on the heap, only the message itself stays in the caller's buffer
+pub type SliceBuilder<'a, P = WantsHeader> = Builder<SliceWriter<'a>, P>;
+
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn into_inner(self) -> &'a mut [u8] {
+        self.buf
+    }
+}
+
+impl Write for SliceWriter<'_> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        // running out of room surfaces as Error::ShortBuffer rather than a partial write
+        let end = self
+            .pos
+            .checked_add(bytes.len())
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| io::Error::other(Error::ShortBuffer))?;
+
+        self.buf[self.pos..end].copy_from_slice(bytes);
+        self.pos = end;
+
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SliceWriter<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(delta) => (self.pos as u64).checked_add_signed(delta),
+            SeekFrom::End(delta) => (self.buf.len() as u64).checked_add_signed(delta),
+        };
+
+        self.pos = pos
+            .and_then(|pos| usize::try_from(pos).ok())
+            .ok_or_else(|| io::Error::from(ErrorKind::InvalidInput))?;
+
+        Ok(self.pos as u64)
+    }
+}
+
+// splits at unescaped dots into each label's offset and its octets, `\.` and `\\` stand for a dot or backslash
+// inside a label (RFC 1035 section 5.1)
+fn split_labels(name: &[u8]) -> Result<Vec<(usize, Vec<u8>)>, Error> {
+    let mut labels = Vec::new();
+    let mut label = Vec::new();
+    let mut label_begin = 0usize;
+    let mut escaped = false;
+
+    for (idx, c) in name.iter().enumerate() {
+        match c {
+            _ if escaped => {
+                label.push(*c);
+                escaped = false;
+            }
+            b'\\' => escaped = true,
+            b'.' => {
+                labels.push((label_begin, mem::take(&mut label)));
+                label_begin = idx + 1;
+            }
+            _ => label.push(*c),
+        }
+    }
+
+    if labels.is_empty() || escaped || !label.is_empty() {
+        return Err(Error::NonCanonicalName);
+    }
+
+    Ok(labels)
+}
+
+// option code and length, then its data
+fn edns_option_len<D: AsRef<[u8]>>(option: &EdnsOption<D>) -> u64 {
+    let data_len = match option {
+        EdnsOption::Nsid(nsid) => nsid.len(),
+        EdnsOption::Cookie { client, server } => client.len() + server.as_ref().map_or(0, Vec::len),
+        EdnsOption::ExtendedError { extra_text, .. } => 2 + extra_text.as_ref().len(),
+        EdnsOption::Unknown { data, .. } => data.as_ref().len(),
+    };
+
+    4 + data_len as u64
+}
+
+type BufferAccess<W> = fn(&mut W) -> &mut Vec<u8>;
+
+struct SizeLimit<W> {
+    max_size: u64,
+    // held back until the OPT is written, so that it is never the record left out
+    opt_reserve: u64,
+    buffer: BufferAccess<W>,
+    full: bool,
+}
+
+// What happens to a record that doesn't fit under the size limit
+#[derive(Copy, Clone)]
+enum Overflow {
+    Truncate,
+    Omit,
+    Fail,
+}
+
+impl Overflow {
+    fn additional(is_opt: bool) -> Self {
+        if is_opt { Overflow::Fail } else { Overflow::Omit }
+    }
+}
+
 pub struct Builder<W: Write + Seek, P> {
     writer: W,
     begin_pos: u64,
@@ -20,6 +154,13 @@ pub struct Builder<W: Write + Seek, P> {
     answers: u16,
     authorities: u16,
     additionals: u16,
+    strict: bool,
+    strict_names: bool,
+    underscore_names: bool,
+    opt: bool,
+    compression_stats: CompressionStats,
+    size_limit: Option<SizeLimit<W>>,
+    max_ttl: Option<u32>,
     _phase: PhantomData<P>,
 }
 
@@ -27,6 +168,10 @@ impl<W: Write + Seek, P> Builder<W, P> {
     pub fn into_inner(self) -> W {
         self.writer
     }
+
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression_stats
+    }
 }
 
 impl<W: Write + Seek, P> Builder<W, P> {
@@ -40,12 +185,78 @@ impl<W: Write + Seek, P> Builder<W, P> {
             answers: self.answers,
             authorities: self.authorities,
             additionals: self.additionals,
+            strict: self.strict,
+            strict_names: self.strict_names,
+            underscore_names: self.underscore_names,
+            opt: self.opt,
+            compression_stats: self.compression_stats,
+            size_limit: self.size_limit,
+            max_ttl: self.max_ttl,
             _phase: PhantomData,
         }
     }
 
     fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
-        self.writer.write_all(bytes)?;
+        if let Some(limit) = &self.size_limit {
+            let end = self.writer.stream_position()? - self.begin_pos + bytes.len() as u64;
+            if end + limit.opt_reserve > limit.max_size {
+                return Err(Error::MessageTooLong);
+            }
+        }
+
+        self.writer.write_all(bytes).map_err(|err| match err.downcast::<Error>() {
+            Ok(err) => err,
+            Err(err) => Error::IoError(err),
+        })
+    }
+
+    // Records that overflow the size limit are cut off again, and once one is left out the rest of the message is too
+    fn pack_within_limit(
+        &mut self,
+        overflow: Overflow,
+        pack: impl FnOnce(&mut Self) -> Result<(), Error>,
+    ) -> Result<bool, Error> {
+        let Some(limit) = &mut self.size_limit else {
+            pack(self)?;
+            return Ok(true);
+        };
+
+        let buffer = limit.buffer;
+        match overflow {
+            Overflow::Fail => limit.opt_reserve = 0,
+            _ if limit.full => return Ok(false),
+            _ => {}
+        }
+
+        let start = self.writer.stream_position()?;
+        match pack(self) {
+            Err(Error::MessageTooLong) if !matches!(overflow, Overflow::Fail) => {
+                self.rewind(start)?;
+
+                // additional data may be left out without setting TC (RFC 2181 section 9)
+                if matches!(overflow, Overflow::Truncate) {
+                    buffer(&mut self.writer)[self.begin_pos as usize + 2] |= (HeaderFlags::TRUNCATED.bits() >> 8) as u8;
+                }
+
+                if let Some(limit) = &mut self.size_limit {
+                    limit.full = true;
+                }
+
+                Ok(false)
+            }
+            result => result.map(|_| true),
+        }
+    }
+
+    fn rewind(&mut self, start: u64) -> Result<(), Error> {
+        if let Some(buffer) = self.size_limit.as_ref().map(|limit| limit.buffer) {
+            buffer(&mut self.writer).truncate(start as usize);
+        }
+        self.writer.seek(SeekFrom::Start(start))?;
+
+        let start = start - self.begin_pos;
+        self.name_ptrs.retain(|_, ptr| (*ptr as u64) < start);
+
         Ok(())
     }
 
@@ -58,46 +269,72 @@ impl<W: Write + Seek, P> Builder<W, P> {
     }
 
     fn pack_name(&mut self, name: &str) -> Result<(), Error> {
+        self.pack_name_with(name, true)
+    }
+
+    fn pack_uncompressed_name(&mut self, name: &str) -> Result<(), Error> {
+        self.pack_name_with(name, false)
+    }
+
+    fn pack_name_with(&mut self, name: &str, compress: bool) -> Result<(), Error> {
         if name == "." {
             return Ok(self.write(&[0])?);
         }
 
         let name = name.as_bytes();
-        if name.last().copied() != Some(b'.') {
-            return Err(Error::NonCanonicalName);
+        let labels = split_labels(name)?;
+
+        // every label gains a length octet in place of its dot, plus the root label
+        if labels.iter().map(|(_, label)| label.len() + 1).sum::<usize>() + 1 > 255 {
+            return Err(Error::NameTooLong);
         }
 
-        let dot_indexes = name
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, c)| if *c == b'.' { Some(idx) } else { None });
+        // letters, digits and hyphens, plus underscores for service labels such as `_dmarc` when allowed
+        if self.strict_names
+            && !name
+                .iter()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'.') || (self.underscore_names && *c == b'_'))
+        {
+            return Err(Error::InvalidNameSegmentBody);
+        }
 
-        let mut segment_begin_index = 0usize;
-        for segment_end_index in dot_indexes {
-            let segment_len = segment_end_index - segment_begin_index;
-            if segment_len == 0 || segment_len >= 1 << 6 {
-                return Err(Error::InvalidNameSegmentSize(segment_len));
+        let mut beyond_pointer_range = false;
+        for (label_begin, label) in labels {
+            if label.is_empty() {
+                return Err(Error::EmptyLabel);
+            }
+            if label.len() >= 1 << 6 {
+                return Err(Error::InvalidNameSegmentSize(label.len()));
             }
 
-            if let Some(ptr) = self.name_ptrs.get(&name[segment_begin_index..]) {
+            if let Some(ptr) = self.name_ptrs.get(&name[label_begin..]).filter(|_| compress) {
                 self.write(&(*ptr | 0xc000).to_be_bytes())?;
 
+                self.compression_stats.compressed += 1;
+                if beyond_pointer_range {
+                    self.compression_stats.beyond_pointer_range += 1;
+                }
+
                 return Ok(());
             }
 
             let new_ptr = self.writer.stream_position()? - self.begin_pos;
             if new_ptr <= (u16::MAX >> 2) as u64 {
-                self.name_ptrs.insert(name[segment_begin_index..].to_vec(), new_ptr as u16);
+                self.name_ptrs.insert(name[label_begin..].to_vec(), new_ptr as u16);
+            } else {
+                beyond_pointer_range = true;
             }
 
-            self.write(&[segment_len as u8])?;
-            self.write(&name[segment_begin_index..segment_end_index])?;
-
-            segment_begin_index = segment_end_index + 1;
+            self.write(&[label.len() as u8])?;
+            self.write(&label)?;
         }
 
         self.write(&[0])?;
 
+        if beyond_pointer_range {
+            self.compression_stats.beyond_pointer_range += 1;
+        }
+
         Ok(())
     }
 
@@ -109,24 +346,155 @@ impl<W: Write + Seek, P> Builder<W, P> {
         Ok(())
     }
 
+    fn pack_texts<D: AsRef<[u8]>>(&mut self, texts: &[D]) -> Result<(), Error> {
+        for txt in texts {
+            let txt = txt.as_ref();
+            if txt.len() > u8::MAX as usize {
+                return Err(Error::TextTooLong);
+            }
+
+            self.write(&[txt.len() as u8])?;
+            self.write(txt)?;
+        }
+
+        Ok(())
+    }
+
+    fn pack_edns_option<D: AsRef<[u8]>>(&mut self, option: &EdnsOption<D>) -> Result<(), Error> {
+        match option {
+            EdnsOption::Nsid(nsid) => {
+                if nsid.len() > u16::MAX as usize {
+                    return Err(Error::InvalidEdnsOption);
+                }
+
+                self.write(&u16::from(EdnsOptionCode::Nsid).to_be_bytes())?;
+                self.write(&(nsid.len() as u16).to_be_bytes())?;
+                self.write(nsid)?;
+            }
+            EdnsOption::Cookie { client, server } => {
+                let server = server.as_deref().unwrap_or_default();
+                if !server.is_empty() && !(8..=32).contains(&server.len()) {
+                    return Err(Error::InvalidEdnsOption);
+                }
+
+                self.write(&u16::from(EdnsOptionCode::Cookie).to_be_bytes())?;
+                self.write(&((client.len() + server.len()) as u16).to_be_bytes())?;
+                self.write(client)?;
+                self.write(server)?;
+            }
+            EdnsOption::ExtendedError { info_code, extra_text } => {
+                let extra_text = extra_text.as_ref();
+                if extra_text.len() > u16::MAX as usize - 2 {
+                    return Err(Error::InvalidEdnsOption);
+                }
+
+                self.write(&u16::from(EdnsOptionCode::ExtendedError).to_be_bytes())?;
+                self.write(&((extra_text.len() + 2) as u16).to_be_bytes())?;
+                self.write(&(*info_code).into().to_be_bytes())?;
+                self.write(extra_text)?;
+            }
+            EdnsOption::Unknown { code, data } => {
+                let data = data.as_ref();
+                if data.len() > u16::MAX as usize {
+                    return Err(Error::InvalidEdnsOption);
+                }
+
+                self.write(&(*code).into().to_be_bytes())?;
+                self.write(&(data.len() as u16).to_be_bytes())?;
+                self.write(data)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clamp_ttl(&self, typ: MaybeUnknown<Type>, ttl: u32) -> u32 {
+        // the OPT ttl field carries the extended rcode and flags
+        match self.max_ttl {
+            Some(max_ttl) if typ != MaybeUnknown::Known(Type::OPT) => ttl.min(max_ttl),
+            _ => ttl,
+        }
+    }
+
+    // rdata is copied verbatim, so names inside it are never compressed
+    fn pack_raw_record(
+        &mut self,
+        name: &str,
+        typ: MaybeUnknown<Type>,
+        class: MaybeUnknown<Class>,
+        ttl: u32,
+        rdlen: Option<u16>,
+        rdata: &[u8],
+    ) -> Result<(), Error> {
+        if rdata.len() > u16::MAX as usize {
+            return Err(Error::InvalidRecordData);
+        }
+
+        self.pack_name(name)?;
+        self.write(&typ.into().to_be_bytes())?;
+        self.write(&class.into().to_be_bytes())?;
+        self.write(&self.clamp_ttl(typ, ttl).to_be_bytes())?;
+        self.write(&rdlen.unwrap_or(rdata.len() as u16).to_be_bytes())?;
+        self.write(rdata)?;
+
+        Ok(())
+    }
+
+    fn pack_resource_visitor(&mut self, resource: &Resource<NameVisitor<'_>, &[u8]>) -> Result<(), Error> {
+        let resource = resource
+            .clone()
+            .try_into_owned::<String, Vec<u8>>()
+            .map_err(|err| match err {
+                EitherError::Left(err) => err,
+                EitherError::Right(err) => match err {},
+            })?;
+
+        self.pack_resource(&resource)
+    }
+
+    fn pack_svc_params<D: AsRef<[u8]>>(&mut self, params: &[SvcParam<D>]) -> Result<(), Error> {
+        let mut params = params
+            .iter()
+            .map(|param| (param.key.into(), param.value.as_ref()))
+            .collect::<Vec<(u16, _)>>();
+        params.sort_by_key(|(key, _)| *key);
+
+        if params.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(Error::InvalidRecordData);
+        }
+
+        if let Some((_, mandatory)) = params.iter().find(|(key, _)| *key == SvcParamKey::Mandatory.into()) {
+            if mandatory.is_empty() || mandatory.len() % 2 != 0 {
+                return Err(Error::InvalidRecordData);
+            }
+
+            for key in mandatory.chunks_exact(2).map(|key| u16::from_be_bytes([key[0], key[1]])) {
+                if key == SvcParamKey::Mandatory.into() || params.binary_search_by_key(&key, |(key, _)| *key).is_err() {
+                    return Err(Error::InvalidRecordData);
+                }
+            }
+        }
+
+        for (key, value) in params {
+            if value.len() > u16::MAX as usize {
+                return Err(Error::InvalidRecordData);
+            }
+
+            self.write(&key.to_be_bytes())?;
+            self.write(&(value.len() as u16).to_be_bytes())?;
+            self.write(value)?;
+        }
+
+        Ok(())
+    }
+
     fn pack_resource<N: AsRef<str>, D: AsRef<[u8]>>(&mut self, resource: &Resource<N, D>) -> Result<(), Error> {
-        let typ = match &resource.data {
-            ResourceData::CNAME { .. } => MaybeUnknown::Known(Type::CNAME),
-            ResourceData::MX { .. } => MaybeUnknown::Known(Type::MX),
-            ResourceData::NS { .. } => MaybeUnknown::Known(Type::NS),
-            ResourceData::PTR { .. } => MaybeUnknown::Known(Type::PTR),
-            ResourceData::SOA { .. } => MaybeUnknown::Known(Type::SOA),
-            ResourceData::TXT { .. } => MaybeUnknown::Known(Type::TXT),
-            ResourceData::SRV { .. } => MaybeUnknown::Known(Type::SRV),
-            ResourceData::A { .. } => MaybeUnknown::Known(Type::A),
-            ResourceData::AAAA { .. } => MaybeUnknown::Known(Type::AAAA),
-            ResourceData::Unknown { typ, .. } => *typ,
-        };
+        let typ = resource.data.typ();
 
         self.pack_name(resource.name.as_ref())?;
         self.write(&typ.into().to_be_bytes())?;
         self.write(&resource.class.into().to_be_bytes())?;
-        self.write(&resource.ttl.to_be_bytes())?;
+        self.write(&self.clamp_ttl(typ, resource.ttl).to_be_bytes())?;
 
         let len_pos = self.writer.stream_position()?;
         self.write(&0u16.to_be_bytes())?;
@@ -162,16 +530,8 @@ impl<W: Write + Seek, P> Builder<W, P> {
                 self.write(&expire.to_be_bytes())?;
                 self.write(&min_ttl.to_be_bytes())?;
             }
-            ResourceData::TXT { txt } => {
-                for txt in txt {
-                    let txt = txt.as_ref();
-                    if txt.len() > u8::MAX as usize {
-                        return Err(Error::TextTooLong);
-                    }
-
-                    self.write(&[txt.len() as u8])?;
-                    self.write(txt)?;
-                }
+            ResourceData::TXT { txt } | ResourceData::SPF { txt } => {
+                self.pack_texts(txt)?;
             }
             ResourceData::SRV {
                 priority,
@@ -184,14 +544,215 @@ impl<W: Write + Seek, P> Builder<W, P> {
                 self.write(&port.to_be_bytes())?;
                 self.pack_name(target.as_ref())?;
             }
+            ResourceData::KX { preference, exchanger } => {
+                self.write(&preference.to_be_bytes())?;
+                self.pack_uncompressed_name(exchanger.as_ref())?;
+            }
+            ResourceData::AFSDB { subtype, hostname } => {
+                self.write(&subtype.to_be_bytes())?;
+                self.pack_uncompressed_name(hostname.as_ref())?;
+            }
+            ResourceData::CERT {
+                cert_type,
+                key_tag,
+                algorithm,
+                certificate,
+            } => {
+                self.write(&(*cert_type).into().to_be_bytes())?;
+                self.write(&key_tag.to_be_bytes())?;
+                self.write(&[*algorithm])?;
+                self.write(certificate.as_ref())?;
+            }
             ResourceData::A { a } => {
                 self.write(&a.octets())?;
             }
             ResourceData::AAAA { aaaa } => {
                 self.write(&aaaa.octets())?;
             }
-            ResourceData::Unknown { data, .. } => {
-                self.write(data.as_ref())?;
+            ResourceData::WKS {
+                address,
+                protocol,
+                bitmap,
+            } => {
+                self.write(&address.octets())?;
+                self.write(&[*protocol])?;
+                self.write(bitmap.as_ref())?;
+            }
+            ResourceData::MINFO { rmailbx, emailbx } => {
+                self.pack_name(rmailbx.as_ref())?;
+                self.pack_name(emailbx.as_ref())?;
+            }
+            ResourceData::RP { mbox, txt } => {
+                self.pack_uncompressed_name(mbox.as_ref())?;
+                self.pack_uncompressed_name(txt.as_ref())?;
+            }
+            ResourceData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                type_bitmap,
+            } => {
+                let (salt, next_hashed) = (salt.as_ref(), next_hashed.as_ref());
+                if salt.len() > u8::MAX as usize || next_hashed.len() > u8::MAX as usize {
+                    return Err(Error::InvalidRecordData);
+                }
+
+                self.write(&[*hash_algorithm, *flags])?;
+                self.write(&iterations.to_be_bytes())?;
+                self.write(&[salt.len() as u8])?;
+                self.write(salt)?;
+                self.write(&[next_hashed.len() as u8])?;
+                self.write(next_hashed)?;
+                self.write(type_bitmap.as_ref())?;
+            }
+            ResourceData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            } => {
+                let salt = salt.as_ref();
+                if salt.len() > u8::MAX as usize {
+                    return Err(Error::InvalidRecordData);
+                }
+
+                self.write(&[*hash_algorithm, *flags])?;
+                self.write(&iterations.to_be_bytes())?;
+                self.write(&[salt.len() as u8])?;
+                self.write(salt)?;
+            }
+            ResourceData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            } => {
+                self.write(&key_tag.to_be_bytes())?;
+                self.write(&[*algorithm, *digest_type])?;
+                self.write(digest.as_ref())?;
+            }
+            ResourceData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+            } => {
+                self.write(&flags.to_be_bytes())?;
+                self.write(&[*protocol, *algorithm])?;
+                self.write(public_key.as_ref())?;
+            }
+            ResourceData::HIP {
+                pk_algorithm,
+                hit,
+                public_key,
+                rendezvous_servers,
+            } => {
+                let (hit, public_key) = (hit.as_ref(), public_key.as_ref());
+                if hit.len() > u8::MAX as usize || public_key.len() > u16::MAX as usize {
+                    return Err(Error::InvalidRecordData);
+                }
+
+                self.write(&[hit.len() as u8, *pk_algorithm])?;
+                self.write(&(public_key.len() as u16).to_be_bytes())?;
+                self.write(hit)?;
+                self.write(public_key)?;
+                for server in rendezvous_servers {
+                    self.pack_uncompressed_name(server.as_ref())?;
+                }
+            }
+            ResourceData::SVCB {
+                priority,
+                target,
+                params,
+            }
+            | ResourceData::HTTPS {
+                priority,
+                target,
+                params,
+            } => {
+                self.write(&priority.to_be_bytes())?;
+                self.pack_uncompressed_name(target.as_ref())?;
+                self.pack_svc_params(params)?;
+            }
+            ResourceData::LOC {
+                version,
+                size,
+                horiz_pre,
+                vert_pre,
+                latitude,
+                longitude,
+                altitude,
+            } => {
+                self.write(&[*version, *size, *horiz_pre, *vert_pre])?;
+                self.write(&latitude.to_be_bytes())?;
+                self.write(&longitude.to_be_bytes())?;
+                self.write(&altitude.to_be_bytes())?;
+            }
+            ResourceData::EUI48(address) => {
+                self.write(address)?;
+            }
+            ResourceData::EUI64(address) => {
+                self.write(address)?;
+            }
+            ResourceData::TSIG {
+                algorithm,
+                time_signed,
+                fudge,
+                mac,
+                original_id,
+                error,
+                other,
+            } => {
+                let (mac, other) = (mac.as_ref(), other.as_ref());
+                if *time_signed >= 1 << 48 || mac.len() > u16::MAX as usize || other.len() > u16::MAX as usize {
+                    return Err(Error::InvalidRecordData);
+                }
+
+                self.pack_uncompressed_name(algorithm.as_ref())?;
+                self.write(&time_signed.to_be_bytes()[2..])?;
+                self.write(&fudge.to_be_bytes())?;
+                self.write(&(mac.len() as u16).to_be_bytes())?;
+                self.write(mac)?;
+                self.write(&original_id.to_be_bytes())?;
+                self.write(&error.to_be_bytes())?;
+                self.write(&(other.len() as u16).to_be_bytes())?;
+                self.write(other)?;
+            }
+            ResourceData::APL { items } => {
+                for item in items {
+                    let afd = item.afd.as_ref();
+                    let afd = &afd[..afd.iter().rposition(|b| *b != 0).map_or(0, |idx| idx + 1)];
+                    if afd.len() > 0x7f {
+                        return Err(Error::InvalidRecordData);
+                    }
+
+                    self.write(&item.family.to_be_bytes())?;
+                    self.write(&[item.prefix, if item.negation { 0x80 } else { 0 } | afd.len() as u8])?;
+                    self.write(afd)?;
+                }
+            }
+            ResourceData::OPT { options } => {
+                for option in options {
+                    self.pack_edns_option(option)?;
+                }
+            }
+            ResourceData::Unknown { typ, data } => {
+                let data = data.as_ref();
+                if self.strict {
+                    let expected = match typ {
+                        MaybeUnknown::Known(Type::A) => Some(4),
+                        MaybeUnknown::Known(Type::AAAA) => Some(16),
+                        _ => None,
+                    };
+
+                    if expected.is_some_and(|len| len != data.len()) {
+                        return Err(Error::InvalidRecordData);
+                    }
+                }
+
+                self.write(data)?;
             }
         }
 
@@ -205,6 +766,7 @@ impl<W: Write + Seek, P> Builder<W, P> {
 }
 
 impl<W: Write + Seek> Builder<W, WantsHeader> {
+    // compression pointers are relative to the writer's current position, so several messages can share one writer
     pub fn new(mut writer: W) -> Result<Self, Error> {
         let begin_pos = writer.stream_position()?;
 
@@ -216,37 +778,151 @@ impl<W: Write + Seek> Builder<W, WantsHeader> {
             answers: 0,
             authorities: 0,
             additionals: 0,
+            strict: false,
+            strict_names: false,
+            underscore_names: false,
+            opt: false,
+            compression_stats: CompressionStats::default(),
+            size_limit: None,
+            max_ttl: None,
             _phase: PhantomData,
         })
     }
 
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn strict_names(mut self, allow_underscore: bool) -> Self {
+        self.strict_names = true;
+        self.underscore_names = allow_underscore;
+        self
+    }
+
+    pub fn with_max_ttl(mut self, max_ttl: u32) -> Self {
+        self.max_ttl = Some(max_ttl);
+        self
+    }
+
     pub fn write_header(mut self, header: Header) -> Result<Builder<W, WantsQuestions>, Error> {
-        let id = header.id;
-        let bits = (if header.resp { 1 << 15 } else { 0 })
-            | (header.opcode & 0b111) << 11
-            | (header.flags & HeaderFlags::all()).bits()
-            | header.rcode.into() & 0b1111;
-
-        self.write(&id.to_be_bytes())?;
-        self.write(&bits.to_be_bytes())?;
-        self.write(&0u16.to_be_bytes())?;
-        self.write(&0u16.to_be_bytes())?;
-        self.write(&0u16.to_be_bytes())?;
-        self.write(&0u16.to_be_bytes())?;
+        self.write(&header.encode())?;
 
         Ok(self.move_to_next_phase())
     }
 }
 
+impl Builder<Cursor<Vec<u8>>, WantsHeader> {
+    pub fn with_max_size(mut self, max_size: u16) -> Self {
+        self.size_limit = Some(SizeLimit {
+            max_size: max_size as u64,
+            opt_reserve: 0,
+            buffer: Cursor::get_mut,
+            full: false,
+        });
+        self
+    }
+
+    // 512 octets when the query has no OPT (RFC 6891), `options` are the ones the OPT of the response will carry
+    pub fn with_max_size_for<B: AsRef<[u8]>, D: AsRef<[u8]>>(
+        self,
+        query: &Packet<B>,
+        options: &[EdnsOption<D>],
+    ) -> Result<Self, Error> {
+        let edns = query.edns()?;
+
+        let mut builder = self.with_max_size(edns.as_ref().map_or(512, |edns| edns.udp_payload_size.max(512)));
+        if let (Some(limit), Some(_)) = (&mut builder.size_limit, edns) {
+            // root owner, type, class, TTL and rdata length
+            limit.opt_reserve = 11 + options.iter().map(edns_option_len).sum::<u64>();
+        }
+
+        Ok(builder)
+    }
+
+    pub fn query(
+        next_id: impl FnOnce() -> u16,
+        name: &str,
+        typ: MaybeUnknown<Type>,
+        class: MaybeUnknown<Class>,
+    ) -> Result<Vec<u8>, Error> {
+        Self::query_with_id(next_id(), name, typ, class)
+    }
+
+    pub fn query_with_id(id: u16, name: &str, typ: MaybeUnknown<Type>, class: MaybeUnknown<Class>) -> Result<Vec<u8>, Error> {
+        Ok(Builder::new(Cursor::new(Vec::with_capacity(512)))?
+            .write_header(Header::query(id).with_flags(HeaderFlags::RECURSION_DESIRED))?
+            .write_question(&Question { name, typ, class })?
+            .finish_questions()?
+            .finish_answers()?
+            .finish_authorities()?
+            .finish_additionals()?
+            .into_inner())
+    }
+
+    pub fn error_response<B: AsRef<[u8]>>(query: &Packet<B>, rcode: RCode, recursion_available: bool) -> Result<Vec<u8>, Error> {
+        let query_header = query.header()?;
+
+        let mut flags = query_header.flags & (HeaderFlags::RECURSION_DESIRED | HeaderFlags::CHECKING_DISABLED);
+        flags.set(HeaderFlags::RECURSION_AVAILABLE, recursion_available);
+
+        let mut builder = Builder::new(Cursor::new(Vec::with_capacity(query.as_ref().len())))?.write_header(Header {
+            id: query_header.id,
+            resp: true,
+            opcode: query_header.opcode,
+            rcode: rcode.into(),
+            flags,
+        })?;
+        for question in query.questions() {
+            builder = builder.write_question(&question?.try_into_owned::<String>()?)?;
+        }
+
+        Ok(builder
+            .finish_questions()?
+            .finish_answers()?
+            .finish_authorities()?
+            .finish_additionals()?
+            .into_inner())
+    }
+
+    pub fn badvers_response<B: AsRef<[u8]>>(query: &Packet<B>, udp_size: u16) -> Result<Vec<u8>, Error> {
+        let query_header = query.header()?;
+
+        // BADVERS is extended rcode 16, its upper eight bits go to the OPT and the lower four, all zero, to the header
+        let mut builder = Builder::new(Cursor::new(Vec::with_capacity(query.as_ref().len())))?.write_header(Header {
+            id: query_header.id,
+            resp: true,
+            opcode: query_header.opcode,
+            rcode: RCode::Success.into(),
+            flags: query_header.flags & (HeaderFlags::RECURSION_DESIRED | HeaderFlags::CHECKING_DISABLED),
+        })?;
+        for question in query.questions() {
+            builder = builder.write_question(&question?.try_into_owned::<String>()?)?;
+        }
+
+        Ok(builder
+            .finish_questions()?
+            .finish_answers()?
+            .finish_authorities()?
+            .write_opt::<&[u8]>(udp_size, (RCODE_BADVERS >> 4) as u8, 0, false, &[])?
+            .finish_additionals()?
+            .into_inner())
+    }
+}
+
 impl<W: Write + Seek> Builder<W, WantsQuestions> {
     pub fn write_question<N: AsRef<str>>(mut self, question: &Question<N>) -> Result<Self, Error> {
-        self.pack_question(question)?;
-
-        self.questions += 1;
+        if self.pack_within_limit(Overflow::Truncate, |builder| builder.pack_question(question))? {
+            self.questions += 1;
+        }
 
         Ok(self)
     }
 
+    pub fn write_any_question(self, name: &str) -> Result<Self, Error> {
+        self.write_question(&Question::any(name))
+    }
+
     pub fn finish_questions(mut self) -> Result<Builder<W, WantsAnswers>, Error> {
         self.write_at(self.begin_pos + 4, &self.questions.to_be_bytes())?;
 
@@ -256,13 +932,71 @@ impl<W: Write + Seek> Builder<W, WantsQuestions> {
 
 impl<W: Write + Seek> Builder<W, WantsAnswers> {
     pub fn write_answer<N: AsRef<str>, D: AsRef<[u8]>>(mut self, answer: &Resource<N, D>) -> Result<Self, Error> {
-        self.pack_resource(answer)?;
+        if self.pack_within_limit(Overflow::Truncate, |builder| builder.pack_resource(answer))? {
+            self.answers += 1;
+        }
+
+        Ok(self)
+    }
+
+    pub fn write_answer_visitor(mut self, answer: &Resource<NameVisitor<'_>, &[u8]>) -> Result<Self, Error> {
+        if self.pack_within_limit(Overflow::Truncate, |builder| builder.pack_resource_visitor(answer))? {
+            self.answers += 1;
+        }
+
+        Ok(self)
+    }
 
-        self.answers += 1;
+    pub fn write_raw_record(
+        mut self,
+        name: &str,
+        typ: MaybeUnknown<Type>,
+        class: MaybeUnknown<Class>,
+        ttl: u32,
+        rdata: &[u8],
+    ) -> Result<Self, Error> {
+        if self.pack_within_limit(Overflow::Truncate, |builder| {
+            builder.pack_raw_record(name, typ, class, ttl, None, rdata)
+        })? {
+            self.answers += 1;
+        }
 
         Ok(self)
     }
 
+    // only meant for crafting malformed messages to test peers
+    pub fn write_raw_rdata(
+        mut self,
+        name: &str,
+        typ: MaybeUnknown<Type>,
+        class: MaybeUnknown<Class>,
+        ttl: u32,
+        rdlen_override: Option<u16>,
+        rdata: &[u8],
+    ) -> Result<Self, Error> {
+        if self.pack_within_limit(Overflow::Truncate, |builder| {
+            builder.pack_raw_record(name, typ, class, ttl, rdlen_override, rdata)
+        })? {
+            self.answers += 1;
+        }
+
+        Ok(self)
+    }
+
+    pub fn write_txt_split(self, name: &str, class: MaybeUnknown<Class>, ttl: u32, data: &[u8]) -> Result<Self, Error> {
+        let mut txt: Vec<&[u8]> = data.chunks(255).collect();
+        if txt.is_empty() {
+            txt.push(&[]);
+        }
+
+        self.write_answer(&Resource {
+            name,
+            class,
+            ttl,
+            data: ResourceData::TXT { txt },
+        })
+    }
+
     pub fn finish_answers(mut self) -> Result<Builder<W, WantsAuthorities>, Error> {
         self.write_at(self.begin_pos + 6, &self.answers.to_be_bytes())?;
 
@@ -272,9 +1006,63 @@ impl<W: Write + Seek> Builder<W, WantsAnswers> {
 
 impl<W: Write + Seek> Builder<W, WantsAuthorities> {
     pub fn write_authority<N: AsRef<str>, D: AsRef<[u8]>>(mut self, authority: &Resource<N, D>) -> Result<Self, Error> {
-        self.pack_resource(authority)?;
+        if self.pack_within_limit(Overflow::Truncate, |builder| builder.pack_resource(authority))? {
+            self.authorities += 1;
+        }
 
-        self.authorities += 1;
+        Ok(self)
+    }
+
+    pub fn write_authority_visitor(mut self, authority: &Resource<NameVisitor<'_>, &[u8]>) -> Result<Self, Error> {
+        if self.pack_within_limit(Overflow::Truncate, |builder| builder.pack_resource_visitor(authority))? {
+            self.authorities += 1;
+        }
+
+        Ok(self)
+    }
+
+    // in a negative response the SOA minimum doubles as the record TTL (RFC 2308)
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_soa_authority(
+        self,
+        zone: &str,
+        mname: &str,
+        rname: &str,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    ) -> Result<Self, Error> {
+        self.write_authority::<_, &[u8]>(&Resource {
+            name: zone,
+            class: Class::INET.into(),
+            ttl: minimum,
+            data: ResourceData::SOA {
+                ns: mname,
+                mbox: rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                min_ttl: minimum,
+            },
+        })
+    }
+
+    pub fn write_raw_record(
+        mut self,
+        name: &str,
+        typ: MaybeUnknown<Type>,
+        class: MaybeUnknown<Class>,
+        ttl: u32,
+        rdata: &[u8],
+    ) -> Result<Self, Error> {
+        if self.pack_within_limit(Overflow::Truncate, |builder| {
+            builder.pack_raw_record(name, typ, class, ttl, None, rdata)
+        })? {
+            self.authorities += 1;
+        }
 
         Ok(self)
     }
@@ -286,11 +1074,149 @@ impl<W: Write + Seek> Builder<W, WantsAuthorities> {
     }
 }
 
+impl<W: Read + Write + Seek> Builder<W, WantsAdditionals> {
+    // the message runs from the writer's current position to its end
+    pub fn resume(mut writer: W) -> Result<Self, Error> {
+        let begin_pos = writer.stream_position()?;
+
+        let mut message = Vec::new();
+        writer.read_to_end(&mut message)?;
+
+        let packet = Packet::new(&message[..])?;
+        let count = |offset: usize| u16::from_be_bytes([message[offset], message[offset + 1]]);
+        let opt = packet
+            .additionals()
+            .any(|additional| additional.is_ok_and(|additional| additional.data.typ() == Type::OPT.into()));
+
+        Ok(Self {
+            writer,
+            begin_pos,
+            name_ptrs: BTreeMap::new(),
+            questions: count(4),
+            answers: count(6),
+            authorities: count(8),
+            additionals: count(10),
+            strict: false,
+            strict_names: false,
+            underscore_names: false,
+            opt,
+            compression_stats: CompressionStats::default(),
+            size_limit: None,
+            max_ttl: None,
+            _phase: PhantomData,
+        })
+    }
+}
+
 impl<W: Write + Seek> Builder<W, WantsAdditionals> {
+    pub fn enable_dnssec(self, udp_size: u16) -> Result<Self, Error> {
+        self.write_opt::<&[u8]>(udp_size, 0, 0, true, &[])
+    }
+
+    pub fn write_opt<D: AsRef<[u8]>>(
+        mut self,
+        udp_size: u16,
+        ext_rcode: u8,
+        version: u8,
+        dnssec_ok: bool,
+        options: &[EdnsOption<D>],
+    ) -> Result<Self, Error> {
+        if self.opt {
+            return Err(Error::DuplicateOpt);
+        }
+
+        let ttl = (ext_rcode as u32) << 24 | (version as u32) << 16 | if dnssec_ok { 0x8000 } else { 0 };
+
+        // options are given up from the last one on before the OPT itself is
+        let mut options = options;
+        let written = loop {
+            let start = self.writer.stream_position()?;
+            match self.pack_within_limit(Overflow::Fail, |builder| builder.pack_opt(udp_size, ttl, options)) {
+                Err(Error::MessageTooLong) if !options.is_empty() => {
+                    self.rewind(start)?;
+                    options = &options[..options.len() - 1];
+                }
+                result => break result?,
+            }
+        };
+
+        if written {
+            self.opt = true;
+            self.additionals += 1;
+        }
+
+        Ok(self)
+    }
+
+    fn pack_opt<D: AsRef<[u8]>>(&mut self, udp_size: u16, ttl: u32, options: &[EdnsOption<D>]) -> Result<(), Error> {
+        self.pack_name(".")?;
+        self.write(&u16::from(Type::OPT).to_be_bytes())?;
+        self.write(&udp_size.to_be_bytes())?;
+        self.write(&ttl.to_be_bytes())?;
+
+        let len_pos = self.writer.stream_position()?;
+        self.write(&0u16.to_be_bytes())?;
+
+        for option in options {
+            self.pack_edns_option(option)?;
+        }
+
+        let writing_pos = self.writer.stream_position()?;
+        if writing_pos - len_pos - 2 > u16::MAX as u64 {
+            return Err(Error::InvalidRecordData);
+        }
+        self.write_at(len_pos, &((writing_pos - len_pos - 2) as u16).to_be_bytes())
+    }
+
+    fn check_opt(&self, typ: MaybeUnknown<Type>) -> Result<bool, Error> {
+        let is_opt = typ == Type::OPT.into();
+        if is_opt && self.opt {
+            return Err(Error::DuplicateOpt);
+        }
+
+        Ok(is_opt)
+    }
+
     pub fn write_additional<N: AsRef<str>, D: AsRef<[u8]>>(mut self, additional: &Resource<N, D>) -> Result<Self, Error> {
-        self.pack_resource(additional)?;
+        let is_opt = self.check_opt(additional.data.typ())?;
+
+        if self.pack_within_limit(Overflow::additional(is_opt), |builder| builder.pack_resource(additional))? {
+            self.opt |= is_opt;
+            self.additionals += 1;
+        }
+
+        Ok(self)
+    }
+
+    pub fn write_additional_visitor(mut self, additional: &Resource<NameVisitor<'_>, &[u8]>) -> Result<Self, Error> {
+        let is_opt = self.check_opt(additional.data.typ())?;
 
-        self.additionals += 1;
+        if self.pack_within_limit(Overflow::additional(is_opt), |builder| {
+            builder.pack_resource_visitor(additional)
+        })? {
+            self.opt |= is_opt;
+            self.additionals += 1;
+        }
+
+        Ok(self)
+    }
+
+    pub fn write_raw_record(
+        mut self,
+        name: &str,
+        typ: MaybeUnknown<Type>,
+        class: MaybeUnknown<Class>,
+        ttl: u32,
+        rdata: &[u8],
+    ) -> Result<Self, Error> {
+        let is_opt = self.check_opt(typ)?;
+
+        if self.pack_within_limit(Overflow::additional(is_opt), |builder| {
+            builder.pack_raw_record(name, typ, class, ttl, None, rdata)
+        })? {
+            self.opt |= is_opt;
+            self.additionals += 1;
+        }
 
         Ok(self)
     }
@@ -300,4 +1226,10 @@ impl<W: Write + Seek> Builder<W, WantsAdditionals> {
 
         Ok(self.writer)
     }
+
+    pub fn finish_additionals_with_len(mut self) -> Result<(W, u64), Error> {
+        let len = self.writer.stream_position()? - self.begin_pos;
+
+        Ok((self.finish_additionals()?, len))
+    }
 }
@@ -0,0 +1,43 @@
+use std::io::Cursor;
+
+use crate::{Builder, EitherError, Error, Header, OwnedResource, Packet, RCode};
+
+// Records are converted through their wire form, so every type either crate understands survives the trip
+
+impl TryFrom<simple_dns::ResourceRecord<'_>> for OwnedResource {
+    type Error = Error;
+
+    fn try_from(record: simple_dns::ResourceRecord<'_>) -> Result<Self, Self::Error> {
+        let mut pkt = simple_dns::Packet::new_reply(0);
+        pkt.answers.push(record);
+
+        let pkt = pkt.build_bytes_vec().map_err(|_| Error::InvalidRecordData)?;
+        let pkt = Packet::new(&pkt[..])?;
+        let answer = pkt.answers().next().ok_or(Error::InvalidRecordData)??;
+
+        answer.try_into_owned().map_err(|err| match err {
+            EitherError::Left(err) => err,
+            EitherError::Right(err) => match err {},
+        })
+    }
+}
+
+impl TryFrom<OwnedResource> for simple_dns::ResourceRecord<'static> {
+    type Error = Error;
+
+    fn try_from(resource: OwnedResource) -> Result<Self, Self::Error> {
+        let pkt = Builder::new(Cursor::new(Vec::new()))?
+            .write_header(Header::response(0, RCode::Success.into()))?
+            .finish_questions()?
+            .write_answer(&resource)?
+            .finish_answers()?
+            .finish_authorities()?
+            .finish_additionals()?
+            .into_inner();
+
+        let pkt = simple_dns::Packet::parse(&pkt).map_err(|_| Error::InvalidRecordData)?;
+        let answer = pkt.answers.into_iter().next().ok_or(Error::InvalidRecordData)?;
+
+        Ok(answer.into_owned())
+    }
+}
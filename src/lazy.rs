@@ -0,0 +1,143 @@
+use std::cell::OnceCell;
+
+use crate::{
+    Error, Header, NameVisitor, Question, Resource,
+    packet::{parse_question, parse_resource, skip_question, skip_resource},
+};
+
+fn cached(cell: &OnceCell<usize>, init: impl FnOnce() -> Result<usize, Error>) -> Result<usize, Error> {
+    if let Some(offset) = cell.get() {
+        return Ok(*offset);
+    }
+
+    let offset = init()?;
+    Ok(*cell.get_or_init(|| offset))
+}
+
+#[derive(Debug, Clone)]
+pub struct LazyPacket<B> {
+    packet: B,
+    answers_offset: OnceCell<usize>,
+    authorities_offset: OnceCell<usize>,
+    additionals_offset: OnceCell<usize>,
+}
+
+impl<B: AsRef<[u8]>> LazyPacket<B> {
+    pub fn new(packet: B) -> Result<Self, Error> {
+        if packet.as_ref().len() < 12 {
+            return Err(Error::ShortBuffer);
+        }
+
+        Ok(Self {
+            packet,
+            answers_offset: OnceCell::new(),
+            authorities_offset: OnceCell::new(),
+            additionals_offset: OnceCell::new(),
+        })
+    }
+
+    pub fn into_inner(self) -> B {
+        self.packet
+    }
+
+    pub fn header(&self) -> Result<Header, Error> {
+        Header::parse(self.packet.as_ref())
+    }
+
+    fn count(&self, index: usize) -> u16 {
+        let packet = self.packet.as_ref();
+
+        // LazyPacket::new guarantees at least a full header
+        u16::from_be_bytes([packet[4 + index * 2], packet[5 + index * 2]])
+    }
+
+    pub fn questions_len(&self) -> u16 {
+        self.count(0)
+    }
+
+    pub fn answers_len(&self) -> u16 {
+        self.count(1)
+    }
+
+    pub fn authorities_len(&self) -> u16 {
+        self.count(2)
+    }
+
+    pub fn additionals_len(&self) -> u16 {
+        self.count(3)
+    }
+
+    fn answers_offset(&self) -> Result<usize, Error> {
+        cached(&self.answers_offset, || {
+            let mut offset = 12;
+            for _ in 0..self.questions_len() {
+                offset = skip_question(self.packet.as_ref(), offset)?;
+            }
+
+            Ok(offset)
+        })
+    }
+
+    fn skip_resources(&self, mut offset: usize, count: u16) -> Result<usize, Error> {
+        for _ in 0..count {
+            offset = skip_resource(self.packet.as_ref(), offset)?;
+        }
+
+        Ok(offset)
+    }
+
+    fn authorities_offset(&self) -> Result<usize, Error> {
+        cached(&self.authorities_offset, || {
+            self.skip_resources(self.answers_offset()?, self.answers_len())
+        })
+    }
+
+    fn additionals_offset(&self) -> Result<usize, Error> {
+        cached(&self.additionals_offset, || {
+            self.skip_resources(self.authorities_offset()?, self.authorities_len())
+        })
+    }
+
+    pub fn questions(&self) -> impl Iterator<Item = Result<Question<NameVisitor<'_>>, Error>> + '_ {
+        let packet = self.packet.as_ref();
+
+        let mut offset = 12;
+        (0..self.questions_len()).map(move |_| {
+            let (question, next_offset) = parse_question(packet, offset)?;
+            offset = next_offset;
+
+            Ok(question)
+        })
+    }
+
+    fn resources(
+        &self,
+        mut offset: usize,
+        count: u16,
+    ) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
+        let packet = self.packet.as_ref();
+
+        (0..count).map(move |_| {
+            let (res, next_offset) = parse_resource(packet, offset)?;
+            offset = next_offset;
+
+            Ok(res)
+        })
+    }
+
+    pub fn answers(&self) -> Result<impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_, Error> {
+        Ok(self.resources(self.answers_offset()?, self.answers_len()))
+    }
+
+    pub fn answers_lenient(&self) -> impl Iterator<Item = Resource<NameVisitor<'_>, &'_ [u8]>> + '_ {
+        self.answers().into_iter().flatten().map_while(Result::ok)
+    }
+
+    pub fn authorities(&self) -> Result<impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_, Error> {
+        Ok(self.resources(self.authorities_offset()?, self.authorities_len()))
+    }
+
+    pub fn additionals(&self) -> Result<impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_, Error> {
+        Ok(self.resources(self.additionals_offset()?, self.additionals_len()))
+    }
+}
@@ -1,14 +1,30 @@
 mod builder;
+#[cfg(feature = "interop")]
+mod interop;
+mod lazy;
+#[cfg(feature = "nsec3")]
+mod nsec3;
+mod octets;
 mod packet;
+mod stream;
+#[cfg(feature = "tokio")]
+mod tokio;
+mod udp;
+mod update;
+mod xfr;
 
 use std::{
     fmt::Debug,
-    net::{Ipv4Addr, Ipv6Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
 };
 
 use num_enum::TryFromPrimitive;
 
-pub use crate::{builder::*, packet::*};
+#[cfg(feature = "nsec3")]
+pub use crate::nsec3::*;
+#[cfg(feature = "tokio")]
+pub use crate::tokio::*;
+pub use crate::{builder::*, lazy::*, octets::*, packet::*, stream::*, udp::*, update::*, xfr::*};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -27,6 +43,9 @@ pub enum Error {
     #[error("text too long")]
     TextTooLong,
 
+    #[error("message too long")]
+    MessageTooLong,
+
     #[error("name is not canonical")]
     NonCanonicalName,
 
@@ -36,11 +55,51 @@ pub enum Error {
     #[error("invalid name segment body")]
     InvalidNameSegmentBody,
 
+    #[error("empty label inside a name")]
+    EmptyLabel,
+
     #[error("too many pointers")]
     TooManyPointers,
 
+    #[error("too many records")]
+    TooManyRecords,
+
     #[error("invalid cursor state")]
     InvalidCursorState,
+
+    #[error("invalid edns option")]
+    InvalidEdnsOption,
+
+    #[error("invalid record data")]
+    InvalidRecordData,
+
+    #[error("duplicate opt record")]
+    DuplicateOpt,
+
+    #[error("opt record outside additionals")]
+    MisplacedOpt,
+
+    #[error("opt record owner is not root")]
+    InvalidOptOwner,
+
+    #[error("invalid zone transfer")]
+    InvalidZoneTransfer,
+
+    #[error("unexpected question count: {0}")]
+    UnexpectedQuestionCount(u16),
+
+    #[error("unexpected source: {0}")]
+    UnexpectedSource(SocketAddr),
+
+    #[error("unexpected opcode: {0}")]
+    UnexpectedOpcode(u16),
+
+    #[error("{section} count mismatch: header declares {declared}, found {actual}")]
+    CountMismatch {
+        section: &'static str,
+        declared: u16,
+        actual: u16,
+    },
 }
 
 bitflags::bitflags! {
@@ -50,6 +109,8 @@ bitflags::bitflags! {
         const TRUNCATED = 1 << 9;
         const RECURSION_DESIRED = 1 << 8;
         const RECURSION_AVAILABLE = 1 << 7;
+        const Z = 1 << 6;
+        #[deprecated(note = "use `HeaderFlags::Z`")]
         const REVERSED = 1 << 6;
         const AUTHENTIC_DATA = 1 << 5;
         const CHECKING_DISABLED = 1 << 4;
@@ -67,6 +128,9 @@ pub enum RCode {
     Refused = 5,
 }
 
+// too wide for the four header rcode bits, so it is carried in the OPT record
+pub const RCODE_BADVERS: u16 = 16;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Header {
     pub id: u16,
@@ -76,7 +140,47 @@ pub struct Header {
     pub flags: HeaderFlags,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+impl Header {
+    pub fn query(id: u16) -> Self {
+        Self {
+            id,
+            resp: false,
+            opcode: 0,
+            rcode: RCode::Success.into(),
+            flags: HeaderFlags::empty(),
+        }
+    }
+
+    pub fn response(id: u16, rcode: MaybeUnknown<RCode>) -> Self {
+        Self {
+            id,
+            resp: true,
+            opcode: 0,
+            rcode,
+            flags: HeaderFlags::empty(),
+        }
+    }
+
+    pub fn with_flags(mut self, flags: HeaderFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    // section counts are left zero
+    pub fn encode(&self) -> [u8; 12] {
+        let bits = (if self.resp { 1 << 15 } else { 0 })
+            | (self.opcode & 0b1111) << 11
+            | (self.flags & HeaderFlags::all()).bits()
+            | self.rcode.into() & 0b1111;
+
+        let mut buf = [0u8; 12];
+        buf[..2].copy_from_slice(&self.id.to_be_bytes());
+        buf[2..4].copy_from_slice(&bits.to_be_bytes());
+        buf
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MaybeUnknown<T: TryFromPrimitive + Into<T::Primitive>> {
     Known(T),
     Unknown(T::Primitive),
@@ -113,7 +217,7 @@ pub enum EitherError<L, R> {
 }
 
 #[repr(u16)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
 pub enum Type {
     A = 1,
     NS = 2,
@@ -122,16 +226,159 @@ pub enum Type {
     PTR = 12,
     MX = 15,
     TXT = 16,
+    AFSDB = 18,
     AAAA = 28,
     SRV = 33,
+    KX = 36,
+    CERT = 37,
     OPT = 41,
+    APL = 42,
+    NSEC3 = 50,
+    NSEC3PARAM = 51,
+    HIP = 55,
+    CDS = 59,
+    CDNSKEY = 60,
+    SVCB = 64,
+    HTTPS = 65,
     WKS = 11,
     HINFO = 13,
     MINFO = 14,
+    RP = 17,
+    LOC = 29,
+    SPF = 99,
+    EUI48 = 108,
+    EUI64 = 109,
+    TSIG = 250,
     AXFR = 252,
     ALL = 255,
 }
 
+// IANA "Resource Record (RR) TYPEs" registry, `*` is spelled ANY
+const TYPE_NAMES: &[(u16, &str)] = &[
+    (1, "A"),
+    (2, "NS"),
+    (3, "MD"),
+    (4, "MF"),
+    (5, "CNAME"),
+    (6, "SOA"),
+    (7, "MB"),
+    (8, "MG"),
+    (9, "MR"),
+    (10, "NULL"),
+    (11, "WKS"),
+    (12, "PTR"),
+    (13, "HINFO"),
+    (14, "MINFO"),
+    (15, "MX"),
+    (16, "TXT"),
+    (17, "RP"),
+    (18, "AFSDB"),
+    (19, "X25"),
+    (20, "ISDN"),
+    (21, "RT"),
+    (22, "NSAP"),
+    (23, "NSAP-PTR"),
+    (24, "SIG"),
+    (25, "KEY"),
+    (26, "PX"),
+    (27, "GPOS"),
+    (28, "AAAA"),
+    (29, "LOC"),
+    (30, "NXT"),
+    (31, "EID"),
+    (32, "NIMLOC"),
+    (33, "SRV"),
+    (34, "ATMA"),
+    (35, "NAPTR"),
+    (36, "KX"),
+    (37, "CERT"),
+    (38, "A6"),
+    (39, "DNAME"),
+    (40, "SINK"),
+    (41, "OPT"),
+    (42, "APL"),
+    (43, "DS"),
+    (44, "SSHFP"),
+    (45, "IPSECKEY"),
+    (46, "RRSIG"),
+    (47, "NSEC"),
+    (48, "DNSKEY"),
+    (49, "DHCID"),
+    (50, "NSEC3"),
+    (51, "NSEC3PARAM"),
+    (52, "TLSA"),
+    (53, "SMIMEA"),
+    (55, "HIP"),
+    (56, "NINFO"),
+    (57, "RKEY"),
+    (58, "TALINK"),
+    (59, "CDS"),
+    (60, "CDNSKEY"),
+    (61, "OPENPGPKEY"),
+    (62, "CSYNC"),
+    (63, "ZONEMD"),
+    (64, "SVCB"),
+    (65, "HTTPS"),
+    (66, "DSYNC"),
+    (67, "HHIT"),
+    (68, "BRID"),
+    (99, "SPF"),
+    (100, "UINFO"),
+    (101, "UID"),
+    (102, "GID"),
+    (103, "UNSPEC"),
+    (104, "NID"),
+    (105, "L32"),
+    (106, "L64"),
+    (107, "LP"),
+    (108, "EUI48"),
+    (109, "EUI64"),
+    (128, "NXNAME"),
+    (249, "TKEY"),
+    (250, "TSIG"),
+    (251, "IXFR"),
+    (252, "AXFR"),
+    (253, "MAILB"),
+    (254, "MAILA"),
+    (255, "ANY"),
+    (256, "URI"),
+    (257, "CAA"),
+    (258, "AVC"),
+    (259, "DOA"),
+    (260, "AMTRELAY"),
+    (261, "RESINFO"),
+    (262, "WALLET"),
+    (263, "CLA"),
+    (264, "IPN"),
+    (32768, "TA"),
+    (32769, "DLV"),
+];
+
+impl Type {
+    pub fn name(&self) -> Option<&'static str> {
+        MaybeUnknown::Known(*self).name()
+    }
+
+    pub fn from_name(name: &str) -> Option<MaybeUnknown<Type>> {
+        MaybeUnknown::from_name(name)
+    }
+}
+
+impl MaybeUnknown<Type> {
+    pub fn name(&self) -> Option<&'static str> {
+        let code = (*self).into();
+
+        TYPE_NAMES.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        TYPE_NAMES
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(code, _)| MaybeUnknown::from(*code))
+    }
+}
+
 #[repr(u16)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
 pub enum Class {
@@ -139,6 +386,7 @@ pub enum Class {
     CSNET = 2,
     CHAOS = 3,
     HESIOD = 4,
+    NONE = 254,
     ANY = 255,
 }
 
@@ -149,6 +397,52 @@ pub struct Question<N> {
     pub class: MaybeUnknown<Class>,
 }
 
+impl<N> Question<N> {
+    // the ANY query type is Type::ALL, to tell it apart from Class::ANY
+    pub fn any(name: N) -> Self {
+        Self {
+            name,
+            typ: Type::ALL.into(),
+            class: Class::INET.into(),
+        }
+    }
+
+    pub fn is_any(&self) -> bool {
+        self.typ == MaybeUnknown::Known(Type::ALL)
+    }
+}
+
+impl Question<String> {
+    pub fn reverse(ip: IpAddr) -> Self {
+        let mut name = String::with_capacity(72);
+
+        match ip {
+            IpAddr::V4(ip) => {
+                for octet in ip.octets().iter().rev() {
+                    name.push_str(&octet.to_string());
+                    name.push('.');
+                }
+                name.push_str("in-addr.arpa.");
+            }
+            IpAddr::V6(ip) => {
+                for octet in ip.octets().iter().rev() {
+                    for nibble in [octet & 0x0f, octet >> 4] {
+                        name.push(char::from_digit(nibble as u32, 16).unwrap());
+                        name.push('.');
+                    }
+                }
+                name.push_str("ip6.arpa.");
+            }
+        }
+
+        Self {
+            name,
+            typ: Type::PTR.into(),
+            class: Class::INET.into(),
+        }
+    }
+}
+
 impl<N: TryInto<String>> Question<N> {
     pub fn try_into_owned<RN: From<String>>(self) -> Result<Question<RN>, N::Error> {
         Ok(Question {
@@ -159,6 +453,163 @@ impl<N: TryInto<String>> Question<N> {
     }
 }
 
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+pub enum EdnsOptionCode {
+    Nsid = 3,
+    Cookie = 10,
+    ExtendedError = 15,
+}
+
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+pub enum ExtendedErrorCode {
+    Other = 0,
+    UnsupportedDnskeyAlgorithm = 1,
+    UnsupportedDsDigestType = 2,
+    StaleAnswer = 3,
+    ForgedAnswer = 4,
+    DnssecIndeterminate = 5,
+    DnssecBogus = 6,
+    SignatureExpired = 7,
+    SignatureNotYetValid = 8,
+    DnskeyMissing = 9,
+    RrsigsMissing = 10,
+    NoZoneKeyBitSet = 11,
+    NsecMissing = 12,
+    CachedError = 13,
+    NotReady = 14,
+    Blocked = 15,
+    Censored = 16,
+    Filtered = 17,
+    Prohibited = 18,
+    StaleNxdomainAnswer = 19,
+    NotAuthoritative = 20,
+    NotSupported = 21,
+    NoReachableAuthority = 22,
+    NetworkError = 23,
+    InvalidData = 24,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EdnsOption<D> {
+    Nsid(Vec<u8>),
+    Cookie {
+        client: [u8; 8],
+        server: Option<Vec<u8>>,
+    },
+    ExtendedError {
+        info_code: MaybeUnknown<ExtendedErrorCode>,
+        extra_text: D,
+    },
+    Unknown {
+        code: MaybeUnknown<EdnsOptionCode>,
+        data: D,
+    },
+}
+
+impl<D> EdnsOption<D> {
+    pub fn nsid_request() -> Self {
+        Self::Nsid(Vec::new())
+    }
+
+    pub fn nsid(&self) -> Option<&[u8]> {
+        match self {
+            Self::Nsid(nsid) => Some(nsid),
+            _ => None,
+        }
+    }
+}
+
+impl<D: TryInto<Vec<u8>>> EdnsOption<D> {
+    pub fn try_into_owned<RD: From<Vec<u8>>>(self) -> Result<EdnsOption<RD>, D::Error> {
+        let option = match self {
+            EdnsOption::Nsid(nsid) => EdnsOption::Nsid(nsid),
+            EdnsOption::Cookie { client, server } => EdnsOption::Cookie { client, server },
+            EdnsOption::ExtendedError { info_code, extra_text } => EdnsOption::ExtendedError {
+                info_code,
+                extra_text: RD::from(extra_text.try_into()?),
+            },
+            EdnsOption::Unknown { code, data } => EdnsOption::Unknown {
+                code,
+                data: RD::from(data.try_into()?),
+            },
+        };
+
+        Ok(option)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edns<D> {
+    pub udp_payload_size: u16,
+    pub ext_rcode: u8,
+    pub version: u8,
+    pub dnssec_ok: bool,
+    pub options: Vec<EdnsOption<D>>,
+}
+
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+pub enum CertType {
+    PKIX = 1,
+    SPKI = 2,
+    PGP = 3,
+    IPKIX = 4,
+    ISPKI = 5,
+    IPGP = 6,
+    ACPKIX = 7,
+    IACPKIX = 8,
+    URI = 253,
+    OID = 254,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AplItem<D> {
+    pub family: u16,
+    pub prefix: u8,
+    pub negation: bool,
+    pub afd: D,
+}
+
+impl<D: TryInto<Vec<u8>>> AplItem<D> {
+    pub fn try_into_owned<RD: From<Vec<u8>>>(self) -> Result<AplItem<RD>, D::Error> {
+        Ok(AplItem {
+            family: self.family,
+            prefix: self.prefix,
+            negation: self.negation,
+            afd: RD::from(self.afd.try_into()?),
+        })
+    }
+}
+
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+pub enum SvcParamKey {
+    Mandatory = 0,
+    Alpn = 1,
+    NoDefaultAlpn = 2,
+    Port = 3,
+    Ipv4Hint = 4,
+    Ech = 5,
+    Ipv6Hint = 6,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SvcParam<D> {
+    pub key: MaybeUnknown<SvcParamKey>,
+    pub value: D,
+}
+
+impl<D: TryInto<Vec<u8>>> SvcParam<D> {
+    pub fn try_into_owned<RD: From<Vec<u8>>>(self) -> Result<SvcParam<RD>, D::Error> {
+        Ok(SvcParam {
+            key: self.key,
+            value: RD::from(self.value.try_into()?),
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ResourceData<N, D> {
     A {
@@ -198,12 +649,469 @@ pub enum ResourceData<N, D> {
         port: u16,
         target: N,
     },
+    KX {
+        preference: u16,
+        exchanger: N,
+    },
+    AFSDB {
+        subtype: u16,
+        hostname: N,
+    },
+    CERT {
+        cert_type: MaybeUnknown<CertType>,
+        key_tag: u16,
+        algorithm: u8,
+        certificate: D,
+    },
+    WKS {
+        address: Ipv4Addr,
+        protocol: u8,
+        bitmap: D,
+    },
+    MINFO {
+        rmailbx: N,
+        emailbx: N,
+    },
+    RP {
+        mbox: N,
+        txt: N,
+    },
+    SPF {
+        txt: Vec<D>,
+    },
+    NSEC3 {
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: D,
+        next_hashed: D,
+        type_bitmap: D,
+    },
+    NSEC3PARAM {
+        hash_algorithm: u8,
+        flags: u8,
+        iterations: u16,
+        salt: D,
+    },
+    HIP {
+        pk_algorithm: u8,
+        hit: D,
+        public_key: D,
+        rendezvous_servers: Vec<N>,
+    },
+    CDS {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: D,
+    },
+    CDNSKEY {
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        public_key: D,
+    },
+    SVCB {
+        priority: u16,
+        target: N,
+        params: Vec<SvcParam<D>>,
+    },
+    HTTPS {
+        priority: u16,
+        target: N,
+        params: Vec<SvcParam<D>>,
+    },
+    LOC {
+        version: u8,
+        size: u8,
+        horiz_pre: u8,
+        vert_pre: u8,
+        latitude: u32,
+        longitude: u32,
+        altitude: u32,
+    },
+    EUI48([u8; 6]),
+    EUI64([u8; 8]),
+    TSIG {
+        algorithm: N,
+        time_signed: u64,
+        fudge: u16,
+        mac: D,
+        original_id: u16,
+        error: u16,
+        other: D,
+    },
+    APL {
+        items: Vec<AplItem<D>>,
+    },
+    OPT {
+        options: Vec<EdnsOption<D>>,
+    },
     Unknown {
         typ: MaybeUnknown<Type>,
         data: D,
     },
 }
 
+impl<N, D> ResourceData<N, D> {
+    pub fn typ(&self) -> MaybeUnknown<Type> {
+        match self {
+            ResourceData::CNAME { .. } => MaybeUnknown::Known(Type::CNAME),
+            ResourceData::MX { .. } => MaybeUnknown::Known(Type::MX),
+            ResourceData::NS { .. } => MaybeUnknown::Known(Type::NS),
+            ResourceData::PTR { .. } => MaybeUnknown::Known(Type::PTR),
+            ResourceData::SOA { .. } => MaybeUnknown::Known(Type::SOA),
+            ResourceData::TXT { .. } => MaybeUnknown::Known(Type::TXT),
+            ResourceData::SRV { .. } => MaybeUnknown::Known(Type::SRV),
+            ResourceData::KX { .. } => MaybeUnknown::Known(Type::KX),
+            ResourceData::AFSDB { .. } => MaybeUnknown::Known(Type::AFSDB),
+            ResourceData::CERT { .. } => MaybeUnknown::Known(Type::CERT),
+            ResourceData::A { .. } => MaybeUnknown::Known(Type::A),
+            ResourceData::AAAA { .. } => MaybeUnknown::Known(Type::AAAA),
+            ResourceData::WKS { .. } => MaybeUnknown::Known(Type::WKS),
+            ResourceData::MINFO { .. } => MaybeUnknown::Known(Type::MINFO),
+            ResourceData::RP { .. } => MaybeUnknown::Known(Type::RP),
+            ResourceData::SPF { .. } => MaybeUnknown::Known(Type::SPF),
+            ResourceData::NSEC3 { .. } => MaybeUnknown::Known(Type::NSEC3),
+            ResourceData::NSEC3PARAM { .. } => MaybeUnknown::Known(Type::NSEC3PARAM),
+            ResourceData::HIP { .. } => MaybeUnknown::Known(Type::HIP),
+            ResourceData::CDS { .. } => MaybeUnknown::Known(Type::CDS),
+            ResourceData::CDNSKEY { .. } => MaybeUnknown::Known(Type::CDNSKEY),
+            ResourceData::SVCB { .. } => MaybeUnknown::Known(Type::SVCB),
+            ResourceData::HTTPS { .. } => MaybeUnknown::Known(Type::HTTPS),
+            ResourceData::LOC { .. } => MaybeUnknown::Known(Type::LOC),
+            ResourceData::EUI48(..) => MaybeUnknown::Known(Type::EUI48),
+            ResourceData::EUI64(..) => MaybeUnknown::Known(Type::EUI64),
+            ResourceData::TSIG { .. } => MaybeUnknown::Known(Type::TSIG),
+            ResourceData::APL { .. } => MaybeUnknown::Known(Type::APL),
+            ResourceData::OPT { .. } => MaybeUnknown::Known(Type::OPT),
+            ResourceData::Unknown { typ, .. } => *typ,
+        }
+    }
+
+    pub fn loc_precisions_cm(&self) -> Option<(u64, u64, u64)> {
+        fn decode(value: u8) -> u64 {
+            (value >> 4) as u64 * 10u64.pow((value & 0x0f) as u32)
+        }
+
+        match self {
+            ResourceData::LOC {
+                size,
+                horiz_pre,
+                vert_pre,
+                ..
+            } => Some((decode(*size), decode(*horiz_pre), decode(*vert_pre))),
+            _ => None,
+        }
+    }
+
+    // algorithm 0 asks the parent to remove the DS RRset (RFC 8078)
+    pub fn is_delete_request(&self) -> bool {
+        matches!(
+            self,
+            ResourceData::CDS { algorithm: 0, .. } | ResourceData::CDNSKEY { algorithm: 0, .. }
+        )
+    }
+}
+
+// a dot inside the local part is escaped as `\.`
+pub fn soa_rname_to_email(name: &str) -> String {
+    let name = name.strip_suffix('.').unwrap_or(name);
+
+    let mut local = String::with_capacity(name.len());
+    let mut chars = name.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => local.push(escaped),
+                None => local.push(c),
+            },
+            '.' => return format!("{}@{}", local, &name[idx + 1..]),
+            _ => local.push(c),
+        }
+    }
+
+    local
+}
+
+pub fn email_to_soa_rname(email: &str) -> String {
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return format!("{}.", email.trim_end_matches('.'));
+    };
+
+    format!("{}.{}.", local.replace('.', "\\."), domain.trim_end_matches('.'))
+}
+
+impl<N: AsRef<str>, D> ResourceData<N, D> {
+    // a root target means the service is not available (RFC 2782)
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, ResourceData::SRV { target, .. } if target.as_ref() == ".")
+    }
+}
+
+impl<N, D: AsRef<[u8]>> ResourceData<N, D> {
+    pub fn concat(&self) -> Vec<u8> {
+        match self {
+            ResourceData::TXT { txt } | ResourceData::SPF { txt } => txt.iter().flat_map(|s| s.as_ref()).copied().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn ports(&self) -> impl Iterator<Item = u16> + '_ {
+        let bitmap = match self {
+            ResourceData::WKS { bitmap, .. } => bitmap.as_ref(),
+            _ => &[],
+        };
+
+        // ports above u16::MAX can't be named, so bits past them are ignored
+        bitmap.iter().take(8192).enumerate().flat_map(|(idx, bits)| {
+            (0..8u16)
+                .filter(move |bit| bits & (0x80 >> bit) != 0)
+                .map(move |bit| idx as u16 * 8 + bit)
+        })
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = MaybeUnknown<Type>> + '_ {
+        let mut bitmap = match self {
+            ResourceData::NSEC3 { type_bitmap, .. } => type_bitmap.as_ref(),
+            _ => &[],
+        };
+
+        std::iter::from_fn(move || {
+            let (&[window, len], rest) = bitmap.split_first_chunk::<2>()?;
+
+            // a window covers 256 types, so it holds 1 to 32 octets (RFC 4034 section 4.1.2)
+            if !(1..=32).contains(&len) {
+                return None;
+            }
+
+            let (bits, rest) = rest.split_at_checked(len as usize)?;
+            bitmap = rest;
+
+            Some(bits.iter().enumerate().flat_map(move |(idx, bits)| {
+                (0..8u16)
+                    .filter(move |bit| bits & (0x80 >> bit) != 0)
+                    .map(move |bit| MaybeUnknown::from((window as u16) << 8 | (idx as u16 * 8 + bit)))
+            }))
+        })
+        .flatten()
+    }
+}
+
+impl<N: TryInto<String>, D> ResourceData<N, D> {
+    pub fn map_names<RN: From<String>>(self) -> Result<ResourceData<RN, D>, N::Error> {
+        let data = match self {
+            ResourceData::A { a } => ResourceData::A { a },
+            ResourceData::NS { ns } => ResourceData::NS {
+                ns: RN::from(ns.try_into()?),
+            },
+            ResourceData::CNAME { cname } => ResourceData::CNAME {
+                cname: RN::from(cname.try_into()?),
+            },
+            ResourceData::SOA {
+                ns,
+                mbox,
+                serial,
+                refresh,
+                retry,
+                expire,
+                min_ttl,
+            } => ResourceData::SOA {
+                ns: RN::from(ns.try_into()?),
+                mbox: RN::from(mbox.try_into()?),
+                serial,
+                refresh,
+                retry,
+                expire,
+                min_ttl,
+            },
+            ResourceData::PTR { ptr } => ResourceData::PTR {
+                ptr: RN::from(ptr.try_into()?),
+            },
+            ResourceData::MX { preference, mx } => ResourceData::MX {
+                preference,
+                mx: RN::from(mx.try_into()?),
+            },
+            ResourceData::TXT { txt } => ResourceData::TXT { txt },
+            ResourceData::AAAA { aaaa } => ResourceData::AAAA { aaaa },
+            ResourceData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => ResourceData::SRV {
+                priority,
+                weight,
+                port,
+                target: RN::from(target.try_into()?),
+            },
+            ResourceData::KX { preference, exchanger } => ResourceData::KX {
+                preference,
+                exchanger: RN::from(exchanger.try_into()?),
+            },
+            ResourceData::AFSDB { subtype, hostname } => ResourceData::AFSDB {
+                subtype,
+                hostname: RN::from(hostname.try_into()?),
+            },
+            ResourceData::CERT {
+                cert_type,
+                key_tag,
+                algorithm,
+                certificate,
+            } => ResourceData::CERT {
+                cert_type,
+                key_tag,
+                algorithm,
+                certificate,
+            },
+            ResourceData::WKS {
+                address,
+                protocol,
+                bitmap,
+            } => ResourceData::WKS {
+                address,
+                protocol,
+                bitmap,
+            },
+            ResourceData::MINFO { rmailbx, emailbx } => ResourceData::MINFO {
+                rmailbx: RN::from(rmailbx.try_into()?),
+                emailbx: RN::from(emailbx.try_into()?),
+            },
+            ResourceData::RP { mbox, txt } => ResourceData::RP {
+                mbox: RN::from(mbox.try_into()?),
+                txt: RN::from(txt.try_into()?),
+            },
+            ResourceData::SPF { txt } => ResourceData::SPF { txt },
+            ResourceData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                type_bitmap,
+            } => ResourceData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                type_bitmap,
+            },
+            ResourceData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            } => ResourceData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            },
+            ResourceData::HIP {
+                pk_algorithm,
+                hit,
+                public_key,
+                rendezvous_servers,
+            } => {
+                let mut new_rendezvous_servers = Vec::with_capacity(rendezvous_servers.len());
+
+                for server in rendezvous_servers {
+                    new_rendezvous_servers.push(RN::from(server.try_into()?));
+                }
+
+                ResourceData::HIP {
+                    pk_algorithm,
+                    hit,
+                    public_key,
+                    rendezvous_servers: new_rendezvous_servers,
+                }
+            }
+            ResourceData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            } => ResourceData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            },
+            ResourceData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+            } => ResourceData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+            },
+            ResourceData::SVCB {
+                priority,
+                target,
+                params,
+            } => ResourceData::SVCB {
+                priority,
+                target: RN::from(target.try_into()?),
+                params,
+            },
+            ResourceData::HTTPS {
+                priority,
+                target,
+                params,
+            } => ResourceData::HTTPS {
+                priority,
+                target: RN::from(target.try_into()?),
+                params,
+            },
+            ResourceData::LOC {
+                version,
+                size,
+                horiz_pre,
+                vert_pre,
+                latitude,
+                longitude,
+                altitude,
+            } => ResourceData::LOC {
+                version,
+                size,
+                horiz_pre,
+                vert_pre,
+                latitude,
+                longitude,
+                altitude,
+            },
+            ResourceData::EUI48(address) => ResourceData::EUI48(address),
+            ResourceData::EUI64(address) => ResourceData::EUI64(address),
+            ResourceData::TSIG {
+                algorithm,
+                time_signed,
+                fudge,
+                mac,
+                original_id,
+                error,
+                other,
+            } => ResourceData::TSIG {
+                algorithm: RN::from(algorithm.try_into()?),
+                time_signed,
+                fudge,
+                mac,
+                original_id,
+                error,
+                other,
+            },
+            ResourceData::APL { items } => ResourceData::APL { items },
+            ResourceData::OPT { options } => ResourceData::OPT { options },
+            ResourceData::Unknown { typ, data } => ResourceData::Unknown { typ, data },
+        };
+
+        Ok(data)
+    }
+}
+
 impl<N, D> ResourceData<N, D>
 where
     N: TryInto<String>,
@@ -265,6 +1173,206 @@ where
                 port,
                 target: RN::from(target.try_into().map_err(|err| EitherError::Left(err))?),
             },
+            ResourceData::KX { preference, exchanger } => ResourceData::KX {
+                preference,
+                exchanger: RN::from(exchanger.try_into().map_err(EitherError::Left)?),
+            },
+            ResourceData::AFSDB { subtype, hostname } => ResourceData::AFSDB {
+                subtype,
+                hostname: RN::from(hostname.try_into().map_err(EitherError::Left)?),
+            },
+            ResourceData::CERT {
+                cert_type,
+                key_tag,
+                algorithm,
+                certificate,
+            } => ResourceData::CERT {
+                cert_type,
+                key_tag,
+                algorithm,
+                certificate: RD::from(certificate.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::WKS {
+                address,
+                protocol,
+                bitmap,
+            } => ResourceData::WKS {
+                address,
+                protocol,
+                bitmap: RD::from(bitmap.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::MINFO { rmailbx, emailbx } => ResourceData::MINFO {
+                rmailbx: RN::from(rmailbx.try_into().map_err(EitherError::Left)?),
+                emailbx: RN::from(emailbx.try_into().map_err(EitherError::Left)?),
+            },
+            ResourceData::RP { mbox, txt } => ResourceData::RP {
+                mbox: RN::from(mbox.try_into().map_err(EitherError::Left)?),
+                txt: RN::from(txt.try_into().map_err(EitherError::Left)?),
+            },
+            ResourceData::SPF { txt } => {
+                let mut new_txt = Vec::with_capacity(txt.len());
+
+                for t in txt {
+                    new_txt.push(RD::from(t.try_into().map_err(EitherError::Right)?));
+                }
+
+                ResourceData::SPF { txt: new_txt }
+            }
+            ResourceData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                type_bitmap,
+            } => ResourceData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt: RD::from(salt.try_into().map_err(EitherError::Right)?),
+                next_hashed: RD::from(next_hashed.try_into().map_err(EitherError::Right)?),
+                type_bitmap: RD::from(type_bitmap.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            } => ResourceData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt: RD::from(salt.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::HIP {
+                pk_algorithm,
+                hit,
+                public_key,
+                rendezvous_servers,
+            } => {
+                let mut new_rendezvous_servers = Vec::with_capacity(rendezvous_servers.len());
+
+                for server in rendezvous_servers {
+                    new_rendezvous_servers.push(RN::from(server.try_into().map_err(EitherError::Left)?));
+                }
+
+                ResourceData::HIP {
+                    pk_algorithm,
+                    hit: RD::from(hit.try_into().map_err(EitherError::Right)?),
+                    public_key: RD::from(public_key.try_into().map_err(EitherError::Right)?),
+                    rendezvous_servers: new_rendezvous_servers,
+                }
+            }
+            ResourceData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            } => ResourceData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest: RD::from(digest.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key,
+            } => ResourceData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key: RD::from(public_key.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::SVCB {
+                priority,
+                target,
+                params,
+            } => {
+                let mut new_params = Vec::with_capacity(params.len());
+
+                for param in params {
+                    new_params.push(param.try_into_owned().map_err(EitherError::Right)?);
+                }
+
+                ResourceData::SVCB {
+                    priority,
+                    target: RN::from(target.try_into().map_err(EitherError::Left)?),
+                    params: new_params,
+                }
+            }
+            ResourceData::HTTPS {
+                priority,
+                target,
+                params,
+            } => {
+                let mut new_params = Vec::with_capacity(params.len());
+
+                for param in params {
+                    new_params.push(param.try_into_owned().map_err(EitherError::Right)?);
+                }
+
+                ResourceData::HTTPS {
+                    priority,
+                    target: RN::from(target.try_into().map_err(EitherError::Left)?),
+                    params: new_params,
+                }
+            }
+            ResourceData::LOC {
+                version,
+                size,
+                horiz_pre,
+                vert_pre,
+                latitude,
+                longitude,
+                altitude,
+            } => ResourceData::LOC {
+                version,
+                size,
+                horiz_pre,
+                vert_pre,
+                latitude,
+                longitude,
+                altitude,
+            },
+            ResourceData::EUI48(address) => ResourceData::EUI48(address),
+            ResourceData::EUI64(address) => ResourceData::EUI64(address),
+            ResourceData::TSIG {
+                algorithm,
+                time_signed,
+                fudge,
+                mac,
+                original_id,
+                error,
+                other,
+            } => ResourceData::TSIG {
+                algorithm: RN::from(algorithm.try_into().map_err(EitherError::Left)?),
+                time_signed,
+                fudge,
+                mac: RD::from(mac.try_into().map_err(EitherError::Right)?),
+                original_id,
+                error,
+                other: RD::from(other.try_into().map_err(EitherError::Right)?),
+            },
+            ResourceData::APL { items } => {
+                let mut new_items = Vec::with_capacity(items.len());
+
+                for item in items {
+                    new_items.push(item.try_into_owned().map_err(EitherError::Right)?);
+                }
+
+                ResourceData::APL { items: new_items }
+            }
+            ResourceData::OPT { options } => {
+                let mut new_options = Vec::with_capacity(options.len());
+
+                for option in options {
+                    new_options.push(option.try_into_owned().map_err(EitherError::Right)?);
+                }
+
+                ResourceData::OPT { options: new_options }
+            }
             ResourceData::Unknown { typ, data } => ResourceData::Unknown {
                 typ,
                 data: RD::from(data.try_into().map_err(|err| EitherError::Right(err))?),
@@ -275,6 +1383,13 @@ where
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Section {
+    Answer,
+    Authority,
+    Additional,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Resource<N, D> {
     pub name: N,
@@ -283,6 +1398,10 @@ pub struct Resource<N, D> {
     pub data: ResourceData<N, D>,
 }
 
+pub type OwnedQuestion = Question<String>;
+pub type OwnedResource = Resource<String, Vec<u8>>;
+pub type OwnedResourceData = ResourceData<String, Vec<u8>>;
+
 impl<N, D> Resource<N, D>
 where
     N: TryInto<String>,
@@ -0,0 +1,25 @@
+use sha1::{Digest, Sha1};
+
+use crate::{Error, NameVisitor, Octets};
+
+pub fn hashed_name<O: Octets + ?Sized>(name: &NameVisitor<'_, O>, salt: &[u8], iterations: u16) -> Result<[u8; 20], Error> {
+    let mut canonical = Vec::with_capacity(64);
+    for segment in name.octet_segments() {
+        let segment = segment?;
+
+        canonical.push(segment.len() as u8);
+        canonical.extend(segment.iter().map(u8::to_ascii_lowercase));
+    }
+    canonical.push(0);
+
+    if canonical.len() > 255 {
+        return Err(Error::NameTooLong);
+    }
+
+    let mut hash: [u8; 20] = Sha1::new().chain_update(&canonical).chain_update(salt).finalize().into();
+    for _ in 0..iterations {
+        hash = Sha1::new().chain_update(hash).chain_update(salt).finalize().into();
+    }
+
+    Ok(hash)
+}
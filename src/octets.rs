@@ -0,0 +1,71 @@
+use std::{borrow::Cow, ops::Range};
+
+pub trait Octets {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn octet(&self, index: usize) -> Option<u8>;
+
+    fn slice(&self, range: Range<usize>) -> Option<Cow<'_, [u8]>>;
+}
+
+impl Octets for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn octet(&self, index: usize) -> Option<u8> {
+        self.get(index).copied()
+    }
+
+    fn slice(&self, range: Range<usize>) -> Option<Cow<'_, [u8]>> {
+        self.get(range).map(Cow::Borrowed)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Chain<'a> {
+    first: &'a [u8],
+    second: &'a [u8],
+}
+
+impl<'a> Chain<'a> {
+    pub fn new(first: &'a [u8], second: &'a [u8]) -> Self {
+        Self { first, second }
+    }
+}
+
+impl Octets for Chain<'_> {
+    fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    fn octet(&self, index: usize) -> Option<u8> {
+        match index.checked_sub(self.first.len()) {
+            None => Some(self.first[index]),
+            Some(index) => self.second.get(index).copied(),
+        }
+    }
+
+    fn slice(&self, range: Range<usize>) -> Option<Cow<'_, [u8]>> {
+        if range.start > range.end || range.end > self.len() {
+            return None;
+        }
+
+        let split = self.first.len();
+        if range.end <= split {
+            Some(Cow::Borrowed(&self.first[range]))
+        } else if range.start >= split {
+            Some(Cow::Borrowed(&self.second[range.start - split..range.end - split]))
+        } else {
+            let mut joined = Vec::with_capacity(range.len());
+            joined.extend_from_slice(&self.first[range.start..]);
+            joined.extend_from_slice(&self.second[..range.end - split]);
+
+            Some(Cow::Owned(joined))
+        }
+    }
+}
@@ -1,39 +1,53 @@
 use std::{
     borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
     fmt::Debug,
-    net::{Ipv4Addr, Ipv6Addr},
-    ops::Deref,
+    hash::{Hash, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ops::{Deref, Range},
 };
 
-use crate::{Class, Error, Header, HeaderFlags, MaybeUnknown, Question, Resource, ResourceData, Type};
+use crate::{
+    AplItem, Class, Edns, EdnsOption, EdnsOptionCode, EitherError, Error, Header, HeaderFlags, LazyPacket, MaybeUnknown, Octets,
+    OwnedResourceData, Question, RCode, Resource, ResourceData, Section, SvcParam, Type,
+};
+
+fn checked_end(offset: usize, len: usize) -> Result<usize, Error> {
+    offset.checked_add(len).ok_or(Error::ShortBuffer)
+}
 
-fn load_bytes<const N: usize>(buffers: &[u8], offset: usize, limit: Option<usize>) -> Result<[u8; N], Error> {
-    if buffers.len() < offset + N {
+fn load_bytes<const N: usize, O: Octets + ?Sized>(buffers: &O, offset: usize, limit: Option<usize>) -> Result<[u8; N], Error> {
+    let end = checked_end(offset, N)?;
+    if buffers.len() < end {
         return Err(Error::ShortBuffer);
     }
 
     if let Some(limit) = limit {
-        if offset + N > limit {
+        if end > limit {
             return Err(Error::PacketSizeMismatch);
         }
     }
 
-    <[u8; N]>::try_from(&buffers[offset..offset + N]).map_err(|_| Error::ShortBuffer)
+    let bytes = buffers.slice(offset..end).ok_or(Error::ShortBuffer)?;
+
+    <[u8; N]>::try_from(bytes.as_ref()).map_err(|_| Error::ShortBuffer)
 }
 
 fn store_bytes<const N: usize>(buffers: &mut [u8], offset: usize, bytes: [u8; N]) -> Result<(), Error> {
-    if buffers.len() < offset + N {
+    let end = checked_end(offset, N)?;
+    if buffers.len() < end {
         return Err(Error::ShortBuffer);
     }
 
-    buffers[offset..offset + N].copy_from_slice(&bytes);
+    buffers[offset..end].copy_from_slice(&bytes);
 
     Ok(())
 }
 
-fn skip_name(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
+fn skip_name<O: Octets + ?Sized>(packet: &O, mut offset: usize) -> Result<usize, Error> {
     loop {
-        let len_or_ptr = load_bytes::<1>(packet, offset, None)?[0];
+        let len_or_ptr = load_bytes::<1, _>(packet, offset, None)?[0];
 
         match len_or_ptr & 0b1100_0000 {
             0b1100_0000 => break Ok(offset + 2),
@@ -51,14 +65,25 @@ fn skip_name(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
     }
 }
 
-fn skip_question(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
+// a name that ends the rdata must fill it, labels left behind mean a zero octet cut it short
+fn trailing_name<O: Octets + ?Sized>(packet: &O, offset: usize, limit: usize) -> Result<NameVisitor<'_, O>, Error> {
+    let end = skip_name(packet, offset)?;
+    match end.cmp(&limit) {
+        Ordering::Less if skip_name(packet, end).ok() == Some(limit) => Err(Error::EmptyLabel),
+        Ordering::Less => Err(Error::PacketSizeMismatch),
+        Ordering::Greater => Err(Error::PacketSizeMismatch),
+        Ordering::Equal => Ok(NameVisitor { packet, offset }),
+    }
+}
+
+pub(crate) fn skip_question(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
     offset = skip_name(packet, offset)?;
     offset += 2; // Type
     offset += 2; // Class
     Ok(offset)
 }
 
-fn skip_resource(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
+pub(crate) fn skip_resource(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
     offset = skip_name(packet, offset)?;
     offset += 2; // Type
     offset += 2; // Class
@@ -69,7 +94,10 @@ fn skip_resource(packet: &[u8], mut offset: usize) -> Result<usize, Error> {
     offset += 2;
 
     // Data
-    offset += len;
+    offset = checked_end(offset, len)?;
+    if offset > packet.len() {
+        return Err(Error::ShortBuffer);
+    }
 
     Ok(offset)
 }
@@ -86,7 +114,28 @@ struct Sections {
     additionals_offset: usize,
 }
 
-fn collect_sections(packet: &[u8]) -> Result<(Sections, usize), Error> {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ParseLimits {
+    pub questions: u16,
+    pub answers: u16,
+    pub authorities: u16,
+    pub additionals: u16,
+    pub records: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            questions: u16::MAX,
+            answers: u16::MAX,
+            authorities: u16::MAX,
+            additionals: u16::MAX,
+            records: usize::MAX,
+        }
+    }
+}
+
+fn collect_sections(packet: &[u8], limits: &ParseLimits) -> Result<(Sections, usize), Error> {
     let mut offset = 4;
 
     let questions = u16::from_be_bytes(load_bytes(packet, offset, None)?);
@@ -101,6 +150,21 @@ fn collect_sections(packet: &[u8]) -> Result<(Sections, usize), Error> {
     let additionals = u16::from_be_bytes(load_bytes(packet, offset, None)?);
     offset += 2;
 
+    if questions > limits.questions
+        || answers > limits.answers
+        || authorities > limits.authorities
+        || additionals > limits.additionals
+        || questions as usize + answers as usize + authorities as usize + additionals as usize > limits.records
+    {
+        return Err(Error::TooManyRecords);
+    }
+
+    // Minimal question is 5 bytes (root name, type, class), minimal resource is 11 bytes (plus ttl, data length)
+    let resources = answers as usize + authorities as usize + additionals as usize;
+    if questions as usize * 5 + resources * 11 > packet.len() - offset {
+        return Err(Error::ShortBuffer);
+    }
+
     let questions_offset = offset;
     for _ in 0..questions {
         offset = skip_question(packet, offset)?;
@@ -136,25 +200,76 @@ fn collect_sections(packet: &[u8]) -> Result<(Sections, usize), Error> {
     ))
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct NameVisitor<'a> {
-    packet: &'a [u8],
+pub struct NameVisitor<'a, O: Octets + ?Sized = [u8]> {
+    packet: &'a O,
     offset: usize,
 }
 
-impl<'a> NameVisitor<'a> {
-    pub fn segments(&self) -> impl Iterator<Item = Result<&'_ [u8], Error>> + '_ {
+impl<O: Octets + ?Sized> Clone for NameVisitor<'_, O> {
+    fn clone(&self) -> Self {
+        Self {
+            packet: self.packet,
+            offset: self.offset,
+        }
+    }
+}
+
+// names compare by their expanded labels ignoring ASCII case, malformed names are only equal to themselves
+impl<O: Octets + ?Sized> PartialEq for NameVisitor<'_, O> {
+    fn eq(&self, other: &Self) -> bool {
+        let (mut lhs, mut rhs) = (self.octet_segments(), other.octet_segments());
+
+        loop {
+            match (lhs.next(), rhs.next()) {
+                (None, None) => return true,
+                (Some(Ok(lhs)), Some(Ok(rhs))) if lhs.eq_ignore_ascii_case(&rhs) => continue,
+                (Some(Err(_)), _) | (_, Some(Err(_))) => {
+                    return std::ptr::eq(self.packet, other.packet) && self.offset == other.offset;
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl<O: Octets + ?Sized> Eq for NameVisitor<'_, O> {}
+
+impl<O: Octets + ?Sized> Hash for NameVisitor<'_, O> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for segment in self.octet_segments() {
+            let Ok(segment) = segment else {
+                // labels are shorter than 64 octets, so this can't be mistaken for a length
+                state.write_u8(u8::MAX);
+                return;
+            };
+
+            state.write_u8(segment.len() as u8);
+            for c in segment.iter() {
+                state.write_u8(c.to_ascii_lowercase());
+            }
+        }
+
+        state.write_u8(0);
+    }
+}
+
+impl<'a, O: Octets + ?Sized> NameVisitor<'a, O> {
+    pub fn new(packet: &'a O, offset: usize) -> Self {
+        Self { packet, offset }
+    }
+
+    fn segment_ranges(&self) -> impl Iterator<Item = Result<Range<usize>, Error>> + '_ {
         let mut offset = self.offset;
         let mut ptr_count = 0;
 
         std::iter::from_fn(move || {
-            fn try_load_segment<'a>(
-                packet: &'a [u8],
+            fn try_load_segment<O: Octets + ?Sized>(
+                packet: &O,
                 offset: &mut usize,
                 ptr_count: &mut usize,
-            ) -> Result<Option<&'a [u8]>, Error> {
+            ) -> Result<Option<Range<usize>>, Error> {
                 loop {
-                    let len_or_ptr = load_bytes::<1>(packet, *offset, None)?[0];
+                    let len_or_ptr = load_bytes::<1, _>(packet, *offset, None)?[0];
                     match len_or_ptr & 0b1100_0000 {
                         0b1100_0000 => {
                             if *ptr_count > 10 {
@@ -163,7 +278,12 @@ impl<'a> NameVisitor<'a> {
 
                             *ptr_count += 1;
                             *offset = ((len_or_ptr & 0b0011_1111) as usize) << 8
-                                | (load_bytes::<1>(packet, *offset + 1, None)?[0] as usize);
+                                | (load_bytes::<1, _>(packet, *offset + 1, None)?[0] as usize);
+
+                            let target = load_bytes::<1, _>(packet, *offset, None)?[0];
+                            if !matches!(target & 0b1100_0000, 0b0000_0000 | 0b1100_0000) {
+                                return Err(Error::InvalidNameSegmentBody);
+                            }
                         }
                         0b0000_0000 => {
                             if len_or_ptr == 0 {
@@ -172,15 +292,15 @@ impl<'a> NameVisitor<'a> {
 
                             *offset += 1;
 
-                            if packet.len() < *offset + len_or_ptr as usize {
+                            let end = checked_end(*offset, len_or_ptr as usize)?;
+                            if end > packet.len() {
                                 return Err(Error::ShortBuffer);
                             }
 
-                            let ret = &packet[*offset..*offset + len_or_ptr as usize];
-
-                            *offset = *offset + len_or_ptr as usize;
+                            let range = *offset..end;
+                            *offset = end;
 
-                            break Ok(Some(ret));
+                            break Ok(Some(range));
                         }
                         _ => {
                             return Err(Error::InvalidNameSegmentBody);
@@ -192,21 +312,133 @@ impl<'a> NameVisitor<'a> {
             try_load_segment(self.packet, &mut offset, &mut ptr_count).transpose()
         })
     }
+
+    pub fn octet_segments(&self) -> impl Iterator<Item = Result<Cow<'a, [u8]>, Error>> + '_ {
+        let packet = self.packet;
+
+        self.segment_ranges()
+            .map(move |range| packet.slice(range?).ok_or(Error::ShortBuffer))
+    }
+
+    pub fn label_count(&self) -> Result<usize, Error> {
+        self.octet_segments().try_fold(0, |count, segment| segment.map(|_| count + 1))
+    }
+
+    pub fn presentation_len(&self) -> Result<usize, Error> {
+        let len = self
+            .octet_segments()
+            .try_fold(0, |len, segment| segment.map(|segment| len + segment.len() + 1))?;
+
+        Ok(len.max(1))
+    }
+
+    pub fn is_compressed(&self) -> Result<bool, Error> {
+        let mut offset = self.offset;
+
+        loop {
+            let len_or_ptr = load_bytes::<1, _>(self.packet, offset, None)?[0];
+
+            match len_or_ptr & 0b1100_0000 {
+                0b1100_0000 => break Ok(true),
+                0b0000_0000 => {
+                    if len_or_ptr == 0 {
+                        break Ok(false);
+                    }
+
+                    offset = checked_end(offset + 1, len_or_ptr as usize)?;
+                }
+                _ => {
+                    return Err(Error::InvalidNameSegmentBody);
+                }
+            }
+        }
+    }
+
+    pub fn labels_owned(&self) -> Result<Vec<String>, Error> {
+        self.octet_segments()
+            .map(|segment| {
+                let segment = segment?;
+                String::from_utf8(segment.into_owned()).map_err(|_| Error::InvalidNameSegmentBody)
+            })
+            .collect()
+    }
+
+    pub fn to_reverse_ip(&self) -> Result<Option<IpAddr>, Error> {
+        let labels = self.octet_segments().collect::<Result<Vec<_>, Error>>()?;
+
+        let Some((digits, [zone, arpa])) = labels.split_last_chunk::<2>() else {
+            return Ok(None);
+        };
+        if !arpa.eq_ignore_ascii_case(b"arpa") {
+            return Ok(None);
+        }
+
+        if zone.eq_ignore_ascii_case(b"in-addr") && digits.len() == 4 {
+            let mut octets = [0u8; 4];
+            for (octet, label) in octets.iter_mut().rev().zip(digits) {
+                // one to three decimal digits without leading zeros, as written by Question::reverse
+                let Some(value) = Some(label)
+                    .filter(|label| (1..=3).contains(&label.len()) && label.iter().all(u8::is_ascii_digit))
+                    .filter(|label| label.len() == 1 || label[0] != b'0')
+                    .and_then(|label| std::str::from_utf8(label).ok()?.parse().ok())
+                else {
+                    return Ok(None);
+                };
+
+                *octet = value;
+            }
+
+            return Ok(Some(Ipv4Addr::from(octets).into()));
+        }
+
+        if zone.eq_ignore_ascii_case(b"ip6") && digits.len() == 32 {
+            let mut octets = [0u8; 16];
+            for (idx, label) in digits.iter().enumerate() {
+                let Some(nibble) = (label.len() == 1 && label[0].is_ascii_hexdigit())
+                    .then(|| (label[0] as char).to_digit(16))
+                    .flatten()
+                else {
+                    return Ok(None);
+                };
+
+                // the first label is the low nibble of the last octet
+                octets[15 - idx / 2] |= (nibble as u8) << (4 * (idx % 2));
+            }
+
+            return Ok(Some(Ipv6Addr::from(octets).into()));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'a> NameVisitor<'a> {
+    pub fn segments(&self) -> impl Iterator<Item = Result<&'a [u8], Error>> + '_ {
+        let packet = self.packet;
+
+        self.segment_ranges()
+            .map(move |range| packet.get(range?).ok_or(Error::ShortBuffer))
+    }
 }
 
-impl TryInto<String> for &'_ NameVisitor<'_> {
+impl<O: Octets + ?Sized> TryInto<String> for &'_ NameVisitor<'_, O> {
     type Error = Error;
 
     fn try_into(self) -> Result<String, Self::Error> {
         let mut s = String::with_capacity(48);
 
-        for segment in self.segments() {
+        // dots and backslashes inside a label are escaped, as the builder reads them
+        for segment in self.octet_segments() {
             let segment = segment?;
-            if segment.contains(&b'.') {
-                return Err(Error::InvalidNameSegmentBody);
+            for c in std::str::from_utf8(&segment)
+                .map_err(|_| Error::InvalidNameSegmentBody)?
+                .chars()
+            {
+                if matches!(c, '.' | '\\') {
+                    s.push('\\');
+                }
+                s.push(c);
             }
-
-            s.push_str(std::str::from_utf8(segment).map_err(|_| Error::InvalidNameSegmentBody)?);
             s.push('.');
         }
 
@@ -218,7 +450,7 @@ impl TryInto<String> for &'_ NameVisitor<'_> {
     }
 }
 
-impl TryInto<String> for NameVisitor<'_> {
+impl<O: Octets + ?Sized> TryInto<String> for NameVisitor<'_, O> {
     type Error = Error;
 
     fn try_into(self) -> Result<String, Self::Error> {
@@ -226,7 +458,7 @@ impl TryInto<String> for NameVisitor<'_> {
     }
 }
 
-impl Debug for NameVisitor<'_> {
+impl<O: Octets + ?Sized> Debug for NameVisitor<'_, O> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = self.try_into().map(Cow::Owned).unwrap_or(Cow::Borrowed("<invalid>"));
 
@@ -234,6 +466,39 @@ impl Debug for NameVisitor<'_> {
     }
 }
 
+impl<O: Octets + ?Sized, D> ResourceData<NameVisitor<'_, O>, D> {
+    // a target that can't be read isn't the root either
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, ResourceData::SRV { target, .. } if target.label_count().is_ok_and(|count| count == 0))
+    }
+}
+
+impl<O: Octets + ?Sized> Question<NameVisitor<'_, O>> {
+    pub fn matches(&self, name: &str, typ: MaybeUnknown<Type>, class: MaybeUnknown<Class>) -> Result<bool, Error> {
+        if self.typ != typ || self.class != class {
+            return Ok(false);
+        }
+
+        let name = name.strip_suffix('.').unwrap_or(name);
+        let mut labels = name.split('.').filter(|_| !name.is_empty());
+        for segment in self.name.octet_segments() {
+            match labels.next() {
+                Some(label) if label.as_bytes().eq_ignore_ascii_case(&segment?) => {}
+                _ => return Ok(false),
+            }
+        }
+
+        Ok(labels.next().is_none())
+    }
+
+    pub fn cache_key(&self) -> Result<(String, MaybeUnknown<Type>, MaybeUnknown<Class>), Error> {
+        let mut name: String = (&self.name).try_into()?;
+        name.make_ascii_lowercase();
+
+        Ok((name, self.typ, self.class))
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Packet<B> {
     packet: B,
@@ -261,14 +526,82 @@ impl<B: AsRef<[u8]>> Debug for Packet<B> {
     }
 }
 
+fn normalize_name(name: &str) -> String {
+    let mut name = name.to_ascii_lowercase();
+    if !name.ends_with('.') {
+        name.push('.');
+    }
+    name
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct NormalizedName(String);
+
+impl From<String> for NormalizedName {
+    fn from(name: String) -> Self {
+        Self(normalize_name(&name))
+    }
+}
+
+// owner, ttl of OPT records only since it carries the extended rcode and flags there, class and data
+type RecordKey<'a> = (
+    NormalizedName,
+    Option<u32>,
+    MaybeUnknown<Class>,
+    ResourceData<NormalizedName, &'a [u8]>,
+);
+
+fn record_keys<'a>(
+    records: impl Iterator<Item = Result<Resource<NameVisitor<'a>, &'a [u8]>, Error>>,
+) -> Result<Vec<RecordKey<'a>>, Error> {
+    records
+        .map(|record| {
+            let record = record?;
+            let ttl = (record.data.typ() == MaybeUnknown::Known(Type::OPT)).then_some(record.ttl);
+
+            Ok((
+                NormalizedName::from(TryInto::<String>::try_into(record.name)?),
+                ttl,
+                record.class,
+                record.data.map_names()?,
+            ))
+        })
+        .collect()
+}
+
+fn same_records(ours: Vec<RecordKey<'_>>, mut theirs: Vec<RecordKey<'_>>) -> bool {
+    if ours.len() != theirs.len() {
+        return false;
+    }
+
+    // record order within a section carries no meaning
+    for record in ours {
+        match theirs.iter().position(|other| *other == record) {
+            Some(idx) => {
+                theirs.swap_remove(idx);
+            }
+            None => return false,
+        }
+    }
+
+    true
+}
+
 impl<B> Packet<B> {
     pub fn new(packet: B) -> Result<Self, Error>
+    where
+        B: AsRef<[u8]>,
+    {
+        Self::new_with_limits(packet, ParseLimits::default())
+    }
+
+    pub fn new_with_limits(packet: B, limits: ParseLimits) -> Result<Self, Error>
     where
         B: AsRef<[u8]>,
     {
         let packet_buf = packet.as_ref();
 
-        let (sections, offset) = collect_sections(packet_buf.as_ref())?;
+        let (sections, offset) = collect_sections(packet_buf.as_ref(), &limits)?;
         if packet_buf.len() > offset {
             return Err(Error::PacketSizeMismatch);
         }
@@ -288,7 +621,7 @@ impl<B> Packet<B> {
     }
 }
 
-fn parse_question(packet: &[u8], mut offset: usize) -> Result<(Question<NameVisitor>, usize), Error> {
+pub(crate) fn parse_question(packet: &[u8], mut offset: usize) -> Result<(Question<NameVisitor>, usize), Error> {
     let name = NameVisitor { packet, offset };
     offset = skip_name(packet, offset)?;
 
@@ -316,13 +649,13 @@ fn parse_resource_data(
 ) -> Result<ResourceData<NameVisitor, &[u8]>, Error> {
     let data = match typ {
         MaybeUnknown::Known(Type::A) => ResourceData::A {
-            a: Ipv4Addr::from(load_bytes::<4>(packet, offset, Some(limit))?),
+            a: Ipv4Addr::from(load_bytes::<4, _>(packet, offset, Some(limit))?),
         },
         MaybeUnknown::Known(Type::NS) => ResourceData::NS {
-            ns: NameVisitor { packet, offset },
+            ns: trailing_name(packet, offset, limit)?,
         },
         MaybeUnknown::Known(Type::CNAME) => ResourceData::CNAME {
-            cname: NameVisitor { packet, offset },
+            cname: trailing_name(packet, offset, limit)?,
         },
         MaybeUnknown::Known(Type::SOA) => {
             let ns = NameVisitor { packet, offset };
@@ -356,37 +689,21 @@ fn parse_resource_data(
             }
         }
         MaybeUnknown::Known(Type::PTR) => ResourceData::PTR {
-            ptr: NameVisitor { packet, offset },
+            ptr: trailing_name(packet, offset, limit)?,
         },
         MaybeUnknown::Known(Type::MX) => {
             let preference = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
             offset += 2;
 
-            let mx = NameVisitor { packet, offset };
+            let mx = trailing_name(packet, offset, limit)?;
 
             ResourceData::MX { preference, mx }
         }
-        MaybeUnknown::Known(Type::TXT) => {
-            let mut texts = Vec::new();
-
-            while offset < limit {
-                let len = load_bytes::<1>(packet, offset, Some(limit))?[0] as usize;
-                offset += 1;
-
-                if offset + len > packet.len() {
-                    return Err(Error::ShortBuffer);
-                } else if offset + len > limit {
-                    return Err(Error::PacketSizeMismatch);
-                }
-
-                texts.push(&packet[offset..offset + len]);
-                offset += len;
-            }
-
-            ResourceData::TXT { txt: texts }
-        }
+        MaybeUnknown::Known(Type::TXT) => ResourceData::TXT {
+            txt: parse_texts(packet, offset, limit)?,
+        },
         MaybeUnknown::Known(Type::AAAA) => ResourceData::AAAA {
-            aaaa: Ipv6Addr::from(load_bytes::<16>(packet, offset, Some(limit))?),
+            aaaa: Ipv6Addr::from(load_bytes::<16, _>(packet, offset, Some(limit))?),
         },
         MaybeUnknown::Known(Type::SRV) => {
             let priority = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
@@ -398,7 +715,7 @@ fn parse_resource_data(
             let port = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
             offset += 2;
 
-            let target = NameVisitor { packet, offset };
+            let target = trailing_name(packet, offset, limit)?;
 
             ResourceData::SRV {
                 priority,
@@ -407,130 +724,937 @@ fn parse_resource_data(
                 target,
             }
         }
-        typ => ResourceData::Unknown {
-            typ,
-            data: &packet[offset..limit],
-        },
-    };
-
-    Ok(data)
-}
+        MaybeUnknown::Known(Type::KX) => {
+            let preference = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-fn parse_resource(packet: &[u8], mut offset: usize) -> Result<(Resource<NameVisitor, &[u8]>, usize), Error> {
-    let name = NameVisitor { packet, offset };
-    offset = skip_name(packet, offset)?;
+            let exchanger = trailing_name(packet, offset, limit)?;
 
-    let typ = u16::from_be_bytes(load_bytes(packet, offset, None)?);
-    offset += 2;
+            ResourceData::KX { preference, exchanger }
+        }
+        MaybeUnknown::Known(Type::AFSDB) => {
+            let subtype = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-    let class = u16::from_be_bytes(load_bytes(packet, offset, None)?);
-    offset += 2;
+            let hostname = trailing_name(packet, offset, limit)?;
 
-    let ttl = u32::from_be_bytes(load_bytes(packet, offset, None)?);
-    offset += 4;
+            ResourceData::AFSDB { subtype, hostname }
+        }
+        MaybeUnknown::Known(Type::CERT) => {
+            let cert_type = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-    let data_len = u16::from_be_bytes(load_bytes(packet, offset, None)?);
-    offset += 2;
+            let key_tag = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-    let data = parse_resource_data(packet, offset, offset + data_len as usize, MaybeUnknown::from(typ))?;
-    offset += data_len as usize;
+            let algorithm = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
 
-    Ok((
-        Resource {
-            name,
-            class: MaybeUnknown::from(class),
-            ttl,
-            data,
-        },
-        offset,
-    ))
-}
+            ResourceData::CERT {
+                cert_type: MaybeUnknown::from(cert_type),
+                key_tag,
+                algorithm,
+                certificate: packet.get(offset..limit).ok_or(Error::ShortBuffer)?,
+            }
+        }
+        MaybeUnknown::Known(Type::WKS) => {
+            let address = Ipv4Addr::from(load_bytes::<4, _>(packet, offset, Some(limit))?);
+            offset += 4;
 
-impl<B: AsRef<[u8]>> Packet<B> {
-    pub fn header(&self) -> Result<Header, Error> {
-        let packet = self.packet.as_ref();
+            let protocol = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
 
-        let id = u16::from_be_bytes(load_bytes(packet, 0, None)?);
-        let bits = u16::from_be_bytes(load_bytes(packet, 2, None)?);
+            ResourceData::WKS {
+                address,
+                protocol,
+                bitmap: packet.get(offset..limit).ok_or(Error::ShortBuffer)?,
+            }
+        }
+        MaybeUnknown::Known(Type::MINFO) => {
+            let rmailbx = NameVisitor { packet, offset };
+            offset = skip_name(packet, offset)?;
 
-        Ok(Header {
-            id,
-            resp: bits & 0b1000_0000 != 0,
-            opcode: (bits & 0b0111_0000) >> 3,
-            rcode: MaybeUnknown::from(bits & 0b0000_1111),
-            flags: HeaderFlags::from_bits_truncate(bits),
-        })
-    }
+            let emailbx = trailing_name(packet, offset, limit)?;
 
-    pub fn questions_len(&self) -> u16 {
-        self.sections.questions
-    }
+            ResourceData::MINFO { rmailbx, emailbx }
+        }
+        MaybeUnknown::Known(Type::RP) => {
+            let mbox = NameVisitor { packet, offset };
+            offset = skip_name(packet, offset)?;
 
-    pub fn answers_len(&self) -> u16 {
-        self.sections.answers
-    }
+            let txt = trailing_name(packet, offset, limit)?;
 
-    pub fn authorities_len(&self) -> u16 {
-        self.sections.authorities
-    }
+            ResourceData::RP { mbox, txt }
+        }
+        MaybeUnknown::Known(Type::SPF) => ResourceData::SPF {
+            txt: parse_texts(packet, offset, limit)?,
+        },
+        MaybeUnknown::Known(Type::NSEC3) => {
+            let hash_algorithm = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
 
-    pub fn additionals_len(&self) -> u16 {
-        self.sections.additionals
-    }
+            let flags = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
 
-    pub fn questions(&self) -> impl Iterator<Item = Result<Question<NameVisitor<'_>>, Error>> + '_ {
-        let packet = self.packet.as_ref();
+            let iterations = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-        let mut offset = self.sections.questions_offset;
-        (0..self.sections.questions).map(move |_| {
-            let (question, next_offset) = parse_question(packet, offset)?;
-            offset = next_offset;
+            let salt = parse_sized_bytes(packet, offset, limit)?;
+            offset += 1 + salt.len();
 
-            Ok(question)
-        })
-    }
+            let next_hashed = parse_sized_bytes(packet, offset, limit)?;
+            offset += 1 + next_hashed.len();
 
-    fn resources(
-        &self,
-        mut offset: usize,
-        count: u16,
-    ) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
-        let packet = self.packet.as_ref();
+            let type_bitmap = packet.get(offset..limit).ok_or(Error::ShortBuffer)?;
+            validate_type_bitmap(type_bitmap)?;
 
-        (0..count).map(move |_| {
-            let (res, next_offset) = parse_resource(packet, offset)?;
-            offset = next_offset;
+            ResourceData::NSEC3 {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+                next_hashed,
+                type_bitmap,
+            }
+        }
+        MaybeUnknown::Known(Type::NSEC3PARAM) => {
+            let hash_algorithm = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
 
-            Ok(res)
-        })
-    }
+            let flags = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
 
-    pub fn answers(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
-        self.resources(self.sections.answers_offset, self.sections.answers)
-    }
+            let iterations = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-    pub fn authorities(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
-        self.resources(self.sections.authorities_offset, self.sections.authorities)
-    }
+            let salt = parse_sized_bytes(packet, offset, limit)?;
+            if offset + 1 + salt.len() != limit {
+                return Err(Error::InvalidRecordData);
+            }
 
-    pub fn additionals(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
-        self.resources(self.sections.additionals_offset, self.sections.additionals)
-    }
-}
+            ResourceData::NSEC3PARAM {
+                hash_algorithm,
+                flags,
+                iterations,
+                salt,
+            }
+        }
+        MaybeUnknown::Known(Type::CDS) => {
+            let key_tag = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-struct Cursor {
-    offset: usize,
-    count: u16,
-    pos: Option<usize>,
-}
+            let [algorithm, digest_type] = load_bytes(packet, offset, Some(limit))?;
+            offset += 2;
 
-impl Cursor {
-    fn next(&mut self, skip: impl FnOnce(usize) -> Result<usize, Error>) -> Result<bool, Error> {
-        if self.count == 0 {
-            return Ok(false);
+            ResourceData::CDS {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest: packet.get(offset..limit).ok_or(Error::ShortBuffer)?,
+            }
         }
+        MaybeUnknown::Known(Type::CDNSKEY) => {
+            let flags = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
 
-        let pos = match self.pos {
+            let [protocol, algorithm] = load_bytes(packet, offset, Some(limit))?;
+            offset += 2;
+
+            ResourceData::CDNSKEY {
+                flags,
+                protocol,
+                algorithm,
+                public_key: packet.get(offset..limit).ok_or(Error::ShortBuffer)?,
+            }
+        }
+        MaybeUnknown::Known(Type::HIP) => {
+            let hit_len = load_bytes::<1, _>(packet, offset, Some(limit))?[0] as usize;
+            offset += 1;
+
+            let pk_algorithm = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+            offset += 1;
+
+            let pk_len = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?) as usize;
+            offset += 2;
+
+            let hit = load_slice(packet, offset, hit_len, limit)?;
+            offset += hit_len;
+
+            let public_key = load_slice(packet, offset, pk_len, limit)?;
+            offset += pk_len;
+
+            let mut rendezvous_servers = Vec::new();
+            while offset < limit {
+                rendezvous_servers.push(NameVisitor { packet, offset });
+                offset = skip_name(packet, offset)?;
+            }
+
+            ResourceData::HIP {
+                pk_algorithm,
+                hit,
+                public_key,
+                rendezvous_servers,
+            }
+        }
+        MaybeUnknown::Known(Type::SVCB) => {
+            let priority = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
+
+            let target = NameVisitor { packet, offset };
+            offset = skip_name(packet, offset)?;
+
+            ResourceData::SVCB {
+                priority,
+                target,
+                params: parse_svc_params(packet, offset, limit)?,
+            }
+        }
+        MaybeUnknown::Known(Type::HTTPS) => {
+            let priority = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
+
+            let target = NameVisitor { packet, offset };
+            offset = skip_name(packet, offset)?;
+
+            ResourceData::HTTPS {
+                priority,
+                target,
+                params: parse_svc_params(packet, offset, limit)?,
+            }
+        }
+        MaybeUnknown::Known(Type::LOC) => {
+            if limit - offset != 16 {
+                return Err(Error::InvalidRecordData);
+            }
+
+            let [version, size, horiz_pre, vert_pre] = load_bytes(packet, offset, Some(limit))?;
+            offset += 4;
+
+            let latitude = u32::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 4;
+
+            let longitude = u32::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 4;
+
+            let altitude = u32::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+
+            ResourceData::LOC {
+                version,
+                size,
+                horiz_pre,
+                vert_pre,
+                latitude,
+                longitude,
+                altitude,
+            }
+        }
+        MaybeUnknown::Known(Type::EUI48) => {
+            if limit - offset != 6 {
+                return Err(Error::InvalidRecordData);
+            }
+
+            ResourceData::EUI48(load_bytes(packet, offset, Some(limit))?)
+        }
+        MaybeUnknown::Known(Type::EUI64) => {
+            if limit - offset != 8 {
+                return Err(Error::InvalidRecordData);
+            }
+
+            ResourceData::EUI64(load_bytes(packet, offset, Some(limit))?)
+        }
+        MaybeUnknown::Known(Type::TSIG) => {
+            let algorithm = NameVisitor { packet, offset };
+            offset = skip_name(packet, offset)?;
+
+            let [a, b, c, d, e, f] = load_bytes(packet, offset, Some(limit))?;
+            let time_signed = u64::from_be_bytes([0, 0, a, b, c, d, e, f]);
+            offset += 6;
+
+            let fudge = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
+
+            let mac_len = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?) as usize;
+            offset += 2;
+
+            let mac = load_slice(packet, offset, mac_len, limit)?;
+            offset += mac_len;
+
+            let original_id = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
+
+            let error = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+            offset += 2;
+
+            let other_len = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?) as usize;
+            offset += 2;
+
+            ResourceData::TSIG {
+                algorithm,
+                time_signed,
+                fudge,
+                mac,
+                original_id,
+                error,
+                other: load_slice(packet, offset, other_len, limit)?,
+            }
+        }
+        MaybeUnknown::Known(Type::APL) => {
+            let mut items = Vec::new();
+
+            while offset < limit {
+                let family = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+                offset += 2;
+
+                let prefix = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+                offset += 1;
+
+                let negation_and_len = load_bytes::<1, _>(packet, offset, Some(limit))?[0];
+                offset += 1;
+
+                let len = (negation_and_len & 0x7f) as usize;
+                items.push(AplItem {
+                    family,
+                    prefix,
+                    negation: negation_and_len & 0x80 != 0,
+                    afd: load_slice(packet, offset, len, limit)?,
+                });
+                offset += len;
+            }
+
+            ResourceData::APL { items }
+        }
+        MaybeUnknown::Known(Type::OPT) => {
+            let mut options = Vec::new();
+
+            while offset < limit {
+                let code = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+                offset += 2;
+
+                let len = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?) as usize;
+                offset += 2;
+
+                options.push(parse_edns_option(
+                    MaybeUnknown::from(code),
+                    load_slice(packet, offset, len, limit)?,
+                )?);
+                offset += len;
+            }
+
+            ResourceData::OPT { options }
+        }
+        typ => ResourceData::Unknown {
+            typ,
+            data: packet.get(offset..limit).ok_or(Error::ShortBuffer)?,
+        },
+    };
+
+    Ok(data)
+}
+
+fn load_slice(packet: &[u8], offset: usize, len: usize, limit: usize) -> Result<&[u8], Error> {
+    let end = checked_end(offset, len)?;
+    if end > packet.len() {
+        return Err(Error::ShortBuffer);
+    } else if end > limit {
+        return Err(Error::PacketSizeMismatch);
+    }
+
+    Ok(&packet[offset..end])
+}
+
+fn parse_svc_params(packet: &[u8], mut offset: usize, limit: usize) -> Result<Vec<SvcParam<&[u8]>>, Error> {
+    let mut params = Vec::new();
+
+    while offset < limit {
+        let key = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?);
+        offset += 2;
+
+        let len = u16::from_be_bytes(load_bytes(packet, offset, Some(limit))?) as usize;
+        offset += 2;
+
+        params.push(SvcParam {
+            key: MaybeUnknown::from(key),
+            value: load_slice(packet, offset, len, limit)?,
+        });
+        offset += len;
+    }
+
+    Ok(params)
+}
+
+fn parse_texts(packet: &[u8], mut offset: usize, limit: usize) -> Result<Vec<&[u8]>, Error> {
+    let mut texts = Vec::new();
+
+    while offset < limit {
+        let len = load_bytes::<1, _>(packet, offset, Some(limit))?[0] as usize;
+        offset += 1;
+
+        // a zero length string may end exactly at the rdata boundary, a longer one must not run past it
+        texts.push(load_slice(packet, offset, len, limit)?);
+        offset += len;
+    }
+
+    Ok(texts)
+}
+
+fn parse_sized_bytes(packet: &[u8], mut offset: usize, limit: usize) -> Result<&[u8], Error> {
+    let len = load_bytes::<1, _>(packet, offset, Some(limit))?[0] as usize;
+    offset += 1;
+
+    load_slice(packet, offset, len, limit)
+}
+
+fn validate_type_bitmap(mut bitmap: &[u8]) -> Result<(), Error> {
+    let mut last_window = None;
+
+    while let Some((&[window, len], rest)) = bitmap.split_first_chunk::<2>() {
+        if last_window.is_some_and(|last| last >= window) || !(1..=32).contains(&len) {
+            return Err(Error::InvalidRecordData);
+        }
+
+        bitmap = rest.get(len as usize..).ok_or(Error::InvalidRecordData)?;
+        last_window = Some(window);
+    }
+
+    if !bitmap.is_empty() {
+        return Err(Error::InvalidRecordData);
+    }
+
+    Ok(())
+}
+
+fn parse_edns_option(code: MaybeUnknown<EdnsOptionCode>, data: &[u8]) -> Result<EdnsOption<&[u8]>, Error> {
+    let option = match code {
+        MaybeUnknown::Known(EdnsOptionCode::Nsid) => EdnsOption::Nsid(data.to_vec()),
+        MaybeUnknown::Known(EdnsOptionCode::Cookie) => {
+            let client =
+                <[u8; 8]>::try_from(data.get(..8).ok_or(Error::InvalidEdnsOption)?).map_err(|_| Error::InvalidEdnsOption)?;
+
+            let server = match data.len() - 8 {
+                0 => None,
+                8..=32 => Some(data[8..].to_vec()),
+                _ => return Err(Error::InvalidEdnsOption),
+            };
+
+            EdnsOption::Cookie { client, server }
+        }
+        MaybeUnknown::Known(EdnsOptionCode::ExtendedError) => {
+            let info_code =
+                <[u8; 2]>::try_from(data.get(..2).ok_or(Error::InvalidEdnsOption)?).map_err(|_| Error::InvalidEdnsOption)?;
+
+            EdnsOption::ExtendedError {
+                info_code: MaybeUnknown::from(u16::from_be_bytes(info_code)),
+                extra_text: &data[2..],
+            }
+        }
+        code => EdnsOption::Unknown { code, data },
+    };
+
+    Ok(option)
+}
+
+pub(crate) fn parse_resource(packet: &[u8], mut offset: usize) -> Result<(Resource<NameVisitor, &[u8]>, usize), Error> {
+    let name = NameVisitor { packet, offset };
+    offset = skip_name(packet, offset)?;
+
+    let typ = u16::from_be_bytes(load_bytes(packet, offset, None)?);
+    offset += 2;
+
+    let class = u16::from_be_bytes(load_bytes(packet, offset, None)?);
+    offset += 2;
+
+    let ttl = u32::from_be_bytes(load_bytes(packet, offset, None)?);
+    offset += 4;
+
+    let data_len = u16::from_be_bytes(load_bytes(packet, offset, None)?);
+    offset += 2;
+
+    let limit = checked_end(offset, data_len as usize)?;
+    if limit > packet.len() {
+        return Err(Error::ShortBuffer);
+    }
+
+    let typ = MaybeUnknown::from(typ);
+    let class = MaybeUnknown::from(class);
+
+    // RFC 2136 prerequisites and deletions carry no rdata, whatever their type
+    let data = if data_len == 0 && matches!(class, MaybeUnknown::Known(Class::ANY | Class::NONE)) && typ != Type::OPT.into() {
+        ResourceData::Unknown { typ, data: &[][..] }
+    } else {
+        parse_resource_data(packet, offset, limit, typ)?
+    };
+    offset = limit;
+
+    Ok((Resource { name, class, ttl, data }, offset))
+}
+
+impl Header {
+    pub fn parse<O: Octets + ?Sized>(packet: &O) -> Result<Self, Error> {
+        let id = u16::from_be_bytes(load_bytes(packet, 0, None)?);
+        let bits = u16::from_be_bytes(load_bytes(packet, 2, None)?);
+
+        Ok(Header {
+            id,
+            resp: bits & 0x8000 != 0,
+            opcode: (bits >> 11) & 0b1111,
+            rcode: MaybeUnknown::from(bits & 0b0000_1111),
+            flags: HeaderFlags::from_bits_truncate(bits),
+        })
+    }
+}
+
+pub fn peek_header(buf: &[u8]) -> Result<Header, Error> {
+    if buf.len() < 12 {
+        return Err(Error::ShortBuffer);
+    }
+
+    Header::parse(buf)
+}
+
+fn owned_records<'a>(records: impl Iterator<Item = Result<Resource<NameVisitor<'a>, &'a [u8]>, Error>>) -> Result<(), Error> {
+    for record in records {
+        record?.try_into_owned::<String, Vec<u8>>().map_err(|err| match err {
+            EitherError::Left(err) => err,
+            EitherError::Right(err) => match err {},
+        })?;
+    }
+
+    Ok(())
+}
+
+fn parse_all_eager(buf: &[u8]) -> Result<(), Error> {
+    let packet = Packet::new(buf)?;
+    packet.header()?;
+
+    for question in packet.questions() {
+        question?.try_into_owned::<String>()?;
+    }
+
+    owned_records(packet.all_records().map(|record| record.map(|(_, record)| record)))
+}
+
+// the lazy parser skips the up-front walk, so it reaches records that Packet::new rejects
+fn parse_all_lazy(buf: &[u8]) -> Result<(), Error> {
+    let packet = LazyPacket::new(buf)?;
+    packet.header()?;
+
+    for question in packet.questions() {
+        question?.try_into_owned::<String>()?;
+    }
+
+    owned_records(packet.answers()?)?;
+    owned_records(packet.authorities()?)?;
+    owned_records(packet.additionals()?)
+}
+
+// walks everything with both parsers and must not panic on any input, the fuzz target relies on it
+pub fn parse_all(buf: &[u8]) -> Result<(), Error> {
+    let eager = parse_all_eager(buf);
+    let lazy = parse_all_lazy(buf);
+
+    eager.and(lazy)
+}
+
+impl<B: AsRef<[u8]>> Packet<B> {
+    pub fn header(&self) -> Result<Header, Error> {
+        Header::parse(self.packet.as_ref())
+    }
+
+    fn header_bits(&self) -> u16 {
+        let packet = self.packet.as_ref();
+
+        // Packet::new guarantees at least a full header
+        u16::from_be_bytes([packet[2], packet[3]])
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.header_bits() & 0x8000 != 0
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.header_bits() & HeaderFlags::TRUNCATED.bits() != 0
+    }
+
+    // the partial record set in a truncated answer should not be cached
+    pub fn should_retry_tcp(&self) -> bool {
+        self.is_truncated()
+    }
+
+    pub fn is_authoritative(&self) -> bool {
+        self.header_bits() & HeaderFlags::AUTHORITATIVE.bits() != 0
+    }
+
+    pub fn is_recursion_available(&self) -> bool {
+        self.header_bits() & HeaderFlags::RECURSION_AVAILABLE.bits() != 0
+    }
+
+    pub fn rcode(&self) -> MaybeUnknown<RCode> {
+        MaybeUnknown::from(self.header_bits() & 0b1111)
+    }
+
+    pub fn is_any_query(&self) -> Result<bool, Error> {
+        for question in self.questions() {
+            if question?.is_any() {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub fn questions_len(&self) -> u16 {
+        self.sections.questions
+    }
+
+    pub fn answers_len(&self) -> u16 {
+        self.sections.answers
+    }
+
+    pub fn authorities_len(&self) -> u16 {
+        self.sections.authorities
+    }
+
+    pub fn additionals_len(&self) -> u16 {
+        self.sections.additionals
+    }
+
+    pub fn verify_counts(&self) -> Result<(), Error> {
+        let packet = self.packet.as_ref();
+        let sections = [
+            ("question", self.sections.questions_offset, self.sections.answers_offset),
+            ("answer", self.sections.answers_offset, self.sections.authorities_offset),
+            (
+                "authority",
+                self.sections.authorities_offset,
+                self.sections.additionals_offset,
+            ),
+            ("additional", self.sections.additionals_offset, packet.len()),
+        ];
+
+        for (idx, (section, mut offset, end)) in sections.into_iter().enumerate() {
+            let declared = u16::from_be_bytes(load_bytes(packet, 4 + idx * 2, None)?);
+
+            let mut actual = 0;
+            while offset < end {
+                offset = if idx == 0 {
+                    skip_question(packet, offset)?
+                } else {
+                    skip_resource(packet, offset)?
+                };
+                actual += 1;
+            }
+
+            if offset != end || actual != declared {
+                return Err(Error::CountMismatch {
+                    section,
+                    declared,
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn questions_bytes(&self) -> &[u8] {
+        &self.packet.as_ref()[self.sections.questions_offset..self.sections.answers_offset]
+    }
+
+    pub fn answers_bytes(&self) -> &[u8] {
+        &self.packet.as_ref()[self.sections.answers_offset..self.sections.authorities_offset]
+    }
+
+    pub fn authorities_bytes(&self) -> &[u8] {
+        &self.packet.as_ref()[self.sections.authorities_offset..self.sections.additionals_offset]
+    }
+
+    pub fn additionals_bytes(&self) -> &[u8] {
+        &self.packet.as_ref()[self.sections.additionals_offset..]
+    }
+
+    pub fn questions(&self) -> impl Iterator<Item = Result<Question<NameVisitor<'_>>, Error>> + '_ {
+        let packet = self.packet.as_ref();
+
+        let mut offset = self.sections.questions_offset;
+        (0..self.sections.questions).map(move |_| {
+            let (question, next_offset) = parse_question(packet, offset)?;
+            offset = next_offset;
+
+            Ok(question)
+        })
+    }
+
+    pub fn single_question(&self) -> Result<Question<NameVisitor<'_>>, Error> {
+        if self.sections.questions != 1 {
+            return Err(Error::UnexpectedQuestionCount(self.sections.questions));
+        }
+
+        Ok(parse_question(self.packet.as_ref(), self.sections.questions_offset)?.0)
+    }
+
+    fn resources(
+        &self,
+        mut offset: usize,
+        count: u16,
+    ) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
+        let packet = self.packet.as_ref();
+
+        (0..count).map(move |_| {
+            let (res, next_offset) = parse_resource(packet, offset)?;
+            offset = next_offset;
+
+            Ok(res)
+        })
+    }
+
+    pub fn answers(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
+        self.resources(self.sections.answers_offset, self.sections.answers)
+    }
+
+    pub fn authorities(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
+        self.resources(self.sections.authorities_offset, self.sections.authorities)
+    }
+
+    pub fn additionals(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
+        self.resources(self.sections.additionals_offset, self.sections.additionals)
+    }
+
+    pub fn all_records(&self) -> impl Iterator<Item = Result<(Section, Resource<NameVisitor<'_>, &'_ [u8]>), Error>> + '_ {
+        let answers = self.answers().map(|res| res.map(|res| (Section::Answer, res)));
+        let authorities = self.authorities().map(|res| res.map(|res| (Section::Authority, res)));
+        let additionals = self.additionals().map(|res| res.map(|res| (Section::Additional, res)));
+
+        answers.chain(authorities).chain(additionals)
+    }
+
+    pub fn type_histogram(&self) -> Result<BTreeMap<MaybeUnknown<Type>, usize>, Error> {
+        let mut histogram = BTreeMap::new();
+        for record in self.all_records() {
+            let (_, record) = record?;
+
+            *histogram.entry(record.data.typ()).or_insert(0) += 1;
+        }
+
+        Ok(histogram)
+    }
+
+    pub fn min_ttl(&self) -> Result<Option<u32>, Error> {
+        let mut min_ttl = None;
+        for record in self.answers().chain(self.authorities()) {
+            let record = record?;
+            if record.data.typ() == MaybeUnknown::Known(Type::OPT) {
+                continue;
+            }
+
+            min_ttl = Some(min_ttl.map_or(record.ttl, |ttl: u32| ttl.min(record.ttl)));
+        }
+
+        Ok(min_ttl)
+    }
+
+    // the lesser of the authority SOA's own TTL and its minimum field (RFC 2308)
+    pub fn negative_ttl(&self) -> Result<Option<u32>, Error> {
+        for record in self.authorities() {
+            let record = record?;
+            if let ResourceData::SOA { min_ttl, .. } = record.data {
+                return Ok(Some(record.ttl.min(min_ttl)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn resolve_cname_chain(&self, start: &str) -> Result<Vec<String>, Error> {
+        let mut chain = vec![start.to_string()];
+        let mut visited = BTreeSet::from([normalize_name(start)]);
+
+        'follow: loop {
+            let current = normalize_name(chain.last().unwrap());
+
+            for answer in self.answers() {
+                let answer = answer?;
+                let ResourceData::CNAME { cname } = answer.data else {
+                    continue;
+                };
+
+                let owner: String = answer.name.try_into()?;
+                if normalize_name(&owner) != current {
+                    continue;
+                }
+
+                let cname: String = cname.try_into()?;
+                if !visited.insert(normalize_name(&cname)) {
+                    break 'follow;
+                }
+
+                chain.push(cname);
+                continue 'follow;
+            }
+
+            break;
+        }
+
+        Ok(chain)
+    }
+
+    // ignores the message id, record TTLs, name case and record order
+    pub fn semantically_eq<C: AsRef<[u8]>>(&self, other: &Packet<C>) -> Result<bool, Error> {
+        let (mut header, mut other_header) = (self.header()?, other.header()?);
+        header.id = 0;
+        other_header.id = 0;
+        if header != other_header {
+            return Ok(false);
+        }
+
+        let questions = self.questions().map(|q| q?.cache_key()).collect::<Result<Vec<_>, Error>>()?;
+        let other_questions = other.questions().map(|q| q?.cache_key()).collect::<Result<Vec<_>, Error>>()?;
+        if questions != other_questions {
+            return Ok(false);
+        }
+
+        Ok(same_records(record_keys(self.answers())?, record_keys(other.answers())?)
+            && same_records(record_keys(self.authorities())?, record_keys(other.authorities())?)
+            && same_records(record_keys(self.additionals())?, record_keys(other.additionals())?))
+    }
+
+    pub fn glue_for(&self, nsdname: &str) -> Result<Vec<OwnedResourceData>, Error> {
+        let nsdname = normalize_name(nsdname);
+
+        let mut glue = Vec::new();
+        for record in self.additionals() {
+            let record = record?;
+            let data = match record.data {
+                ResourceData::A { a } => ResourceData::A { a },
+                ResourceData::AAAA { aaaa } => ResourceData::AAAA { aaaa },
+                _ => continue,
+            };
+
+            let owner: String = record.name.try_into()?;
+            if normalize_name(&owner) == nsdname {
+                glue.push(data);
+            }
+        }
+
+        Ok(glue)
+    }
+
+    pub fn first_a(&self) -> Result<Option<Ipv4Addr>, Error> {
+        self.first_address(|data| match data {
+            ResourceData::A { a } => Some(*a),
+            _ => None,
+        })
+    }
+
+    pub fn first_aaaa(&self) -> Result<Option<Ipv6Addr>, Error> {
+        self.first_address(|data| match data {
+            ResourceData::AAAA { aaaa } => Some(*aaaa),
+            _ => None,
+        })
+    }
+
+    fn first_address<T>(&self, extract: impl Fn(&ResourceData<NameVisitor<'_>, &[u8]>) -> Option<T>) -> Result<Option<T>, Error> {
+        let owners = match self.questions().next() {
+            Some(question) => {
+                let name: String = question?.name.try_into()?;
+                Some(
+                    self.resolve_cname_chain(&name)?
+                        .iter()
+                        .map(|name| normalize_name(name))
+                        .collect::<BTreeSet<_>>(),
+                )
+            }
+            None => None,
+        };
+
+        for answer in self.answers() {
+            let answer = answer?;
+            let Some(address) = extract(&answer.data) else {
+                continue;
+            };
+
+            if let Some(owners) = &owners {
+                let owner: String = answer.name.try_into()?;
+                if !owners.contains(&normalize_name(&owner)) {
+                    continue;
+                }
+            }
+
+            return Ok(Some(address));
+        }
+
+        Ok(None)
+    }
+
+    pub fn check_edns_constraints(&self) -> Result<(), Error> {
+        let mut opt = false;
+        for record in self.all_records() {
+            let (section, record) = record?;
+            if record.data.typ() != MaybeUnknown::Known(Type::OPT) {
+                continue;
+            }
+
+            if section != Section::Additional {
+                return Err(Error::MisplacedOpt);
+            }
+            if record.name.label_count()? != 0 {
+                return Err(Error::InvalidOptOwner);
+            }
+            if opt {
+                return Err(Error::DuplicateOpt);
+            }
+
+            opt = true;
+        }
+
+        Ok(())
+    }
+
+    pub fn dnssec_ok(&self) -> Result<bool, Error> {
+        Ok(self.edns()?.is_some_and(|edns| edns.dnssec_ok))
+    }
+
+    pub fn edns(&self) -> Result<Option<Edns<&'_ [u8]>>, Error> {
+        let mut edns = None;
+
+        for additional in self.additionals() {
+            let additional = additional?;
+
+            if let ResourceData::OPT { options } = additional.data {
+                if edns.is_some() {
+                    return Err(Error::DuplicateOpt);
+                }
+
+                edns = Some(Edns {
+                    udp_payload_size: additional.class.into(),
+                    ext_rcode: (additional.ttl >> 24) as u8,
+                    version: (additional.ttl >> 16) as u8,
+                    dnssec_ok: additional.ttl & 0x8000 != 0,
+                    options,
+                });
+            }
+        }
+
+        Ok(edns)
+    }
+
+    pub fn edns_version(&self) -> Result<Option<u8>, Error> {
+        Ok(self.edns()?.map(|edns| edns.version))
+    }
+}
+
+struct Cursor {
+    offset: usize,
+    count: u16,
+    pos: Option<usize>,
+}
+
+impl Cursor {
+    fn next(&mut self, skip: impl FnOnce(usize) -> Result<usize, Error>) -> Result<bool, Error> {
+        if self.count == 0 {
+            return Ok(false);
+        }
+
+        let pos = match self.pos {
             None => self.offset,
             Some(prev_pos) => skip(prev_pos)?,
         };
@@ -596,6 +1720,10 @@ impl<'a> ResourcesCursor<'a> {
         Ok(resource)
     }
 
+    pub fn offset(&self) -> Result<usize, Error> {
+        self.cursor.pos()
+    }
+
     pub fn set_class(&mut self, class: MaybeUnknown<Class>) -> Result<(), Error> {
         let mut offset = skip_name(self.packet, self.cursor.pos()?)?;
         offset += 2; // Type
@@ -619,7 +1747,10 @@ impl<'a> ResourcesCursor<'a> {
 impl<B: AsMut<[u8]>> Packet<B> {
     pub fn set_header(&mut self, header: Header) -> Result<(), Error> {
         let id = header.id;
-        let bits = (header.flags & HeaderFlags::all()).bits() | (header.opcode & 0b111) << 3 | (header.rcode.into() & 0b1111);
+        let bits = (if header.resp { 1 << 15 } else { 0 })
+            | (header.opcode & 0b1111) << 11
+            | (header.flags & HeaderFlags::all()).bits()
+            | header.rcode.into() & 0b1111;
 
         let packet = self.packet.as_mut();
         store_bytes(packet, 0, id.to_be_bytes())?;
@@ -627,6 +1758,62 @@ impl<B: AsMut<[u8]>> Packet<B> {
         Ok(())
     }
 
+    // section offsets computed by Packet::new are not refreshed after edits
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        self.packet.as_mut()
+    }
+
+    fn update_header_bits(&mut self, update: impl FnOnce(u16) -> u16) {
+        let packet = self.packet.as_mut();
+
+        // Packet::new guarantees at least a full header
+        let bits = update(u16::from_be_bytes([packet[2], packet[3]]));
+        packet[2..4].copy_from_slice(&bits.to_be_bytes());
+    }
+
+    pub fn set_flags(&mut self, flags: HeaderFlags) {
+        self.update_header_bits(|bits| bits & !HeaderFlags::all().bits() | (flags & HeaderFlags::all()).bits());
+    }
+
+    pub fn set_flag(&mut self, flag: HeaderFlags, on: bool) {
+        let flag = (flag & HeaderFlags::all()).bits();
+
+        self.update_header_bits(|bits| if on { bits | flag } else { bits & !flag });
+    }
+
+    pub fn set_rcode(&mut self, rcode: MaybeUnknown<RCode>) {
+        self.update_header_bits(|bits| bits & !0b1111 | rcode.into() & 0b1111);
+    }
+
+    fn update_ttls(&mut self, mut update: impl FnMut(u32) -> u32) -> Result<(), Error> {
+        let packet = self.packet.as_mut();
+        let count = self.sections.answers as usize + self.sections.authorities as usize + self.sections.additionals as usize;
+
+        let mut offset = self.sections.answers_offset;
+        for _ in 0..count {
+            let typ_offset = skip_name(packet, offset)?;
+            let ttl_offset = typ_offset + 4;
+
+            // OPT reuses the TTL field for extended rcode and flags
+            if u16::from_be_bytes(load_bytes(packet, typ_offset, None)?) != Type::OPT.into() {
+                let ttl = u32::from_be_bytes(load_bytes(packet, ttl_offset, None)?);
+                store_bytes(packet, ttl_offset, update(ttl).to_be_bytes())?;
+            }
+
+            offset = skip_resource(packet, offset)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_all_ttls(&mut self, ttl: u32) -> Result<(), Error> {
+        self.update_ttls(|_| ttl)
+    }
+
+    pub fn age_ttls(&mut self, elapsed_secs: u32) -> Result<(), Error> {
+        self.update_ttls(|ttl| ttl.saturating_sub(elapsed_secs))
+    }
+
     pub fn questions_cursor(&mut self) -> QuestionsCursor<'_> {
         QuestionsCursor {
             packet: self.packet.as_mut(),
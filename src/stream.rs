@@ -0,0 +1,24 @@
+use std::io::{Read, Write};
+
+use crate::Error;
+
+pub fn read_message<R: Read>(r: &mut R) -> Result<Vec<u8>, Error> {
+    let mut len = [0u8; 2];
+    r.read_exact(&mut len)?;
+
+    let mut message = vec![0u8; u16::from_be_bytes(len) as usize];
+    r.read_exact(&mut message)?;
+
+    Ok(message)
+}
+
+pub fn write_message<W: Write>(w: &mut W, message: &[u8]) -> Result<(), Error> {
+    if message.len() > u16::MAX as usize {
+        return Err(Error::MessageTooLong);
+    }
+
+    w.write_all(&(message.len() as u16).to_be_bytes())?;
+    w.write_all(message)?;
+
+    Ok(())
+}
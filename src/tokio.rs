@@ -0,0 +1,24 @@
+use ::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::Error;
+
+pub async fn read_tcp_message<R: AsyncRead + Unpin>(r: &mut R) -> Result<Vec<u8>, Error> {
+    let mut len = [0u8; 2];
+    r.read_exact(&mut len).await?;
+
+    let mut message = vec![0u8; u16::from_be_bytes(len) as usize];
+    r.read_exact(&mut message).await?;
+
+    Ok(message)
+}
+
+pub async fn write_tcp_message<W: AsyncWrite + Unpin>(w: &mut W, message: &[u8]) -> Result<(), Error> {
+    if message.len() > u16::MAX as usize {
+        return Err(Error::MessageTooLong);
+    }
+
+    w.write_all(&(message.len() as u16).to_be_bytes()).await?;
+    w.write_all(message).await?;
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use std::net::UdpSocket;
+
+use crate::{Error, Packet};
+
+pub fn recv_packet<'a>(sock: &UdpSocket, buf: &'a mut [u8]) -> Result<Packet<&'a [u8]>, Error> {
+    let peer = sock.peer_addr()?;
+
+    let (n, source) = sock.recv_from(buf)?;
+    if source != peer {
+        return Err(Error::UnexpectedSource(source));
+    }
+
+    Packet::new(&buf[..n])
+}
@@ -0,0 +1,114 @@
+use std::io::{Seek, Write};
+
+use crate::{
+    Builder, Class, Error, MaybeUnknown, NameVisitor, Packet, Question, Resource, Type, WantsAnswers, WantsAuthorities,
+    WantsQuestions,
+};
+
+pub const OPCODE_UPDATE: u16 = 5;
+
+// as printed by dig
+pub fn section_labels(opcode: u16) -> [&'static str; 4] {
+    match opcode {
+        OPCODE_UPDATE => ["ZONE", "PREREQ", "UPDATE", "ADDITIONAL"],
+        _ => ["QUESTION", "ANSWER", "AUTHORITY", "ADDITIONAL"],
+    }
+}
+
+pub struct UpdateView<'a, B> {
+    packet: &'a Packet<B>,
+}
+
+impl<'a, B: AsRef<[u8]>> UpdateView<'a, B> {
+    pub fn new(packet: &'a Packet<B>) -> Result<Self, Error> {
+        let opcode = packet.header()?.opcode;
+        if opcode != OPCODE_UPDATE {
+            return Err(Error::UnexpectedOpcode(opcode));
+        }
+
+        Ok(Self { packet })
+    }
+
+    pub fn zone(&self) -> Result<Question<NameVisitor<'a>>, Error> {
+        self.packet.single_question()
+    }
+
+    pub fn prerequisites(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'a>, &'a [u8]>, Error>> + 'a {
+        self.packet.answers()
+    }
+
+    pub fn updates(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'a>, &'a [u8]>, Error>> + 'a {
+        self.packet.authorities()
+    }
+
+    pub fn additionals(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'a>, &'a [u8]>, Error>> + 'a {
+        self.packet.additionals()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Prerequisite<N, D> {
+    RRsetExists { name: N, typ: MaybeUnknown<Type> },
+    RRsetMatches(Resource<N, D>),
+    RRsetNotExists { name: N, typ: MaybeUnknown<Type> },
+    NameInUse(N),
+    NameNotInUse(N),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Update<N, D> {
+    Add(Resource<N, D>),
+    DeleteRRset { name: N, typ: MaybeUnknown<Type> },
+    DeleteName(N),
+    Delete(Resource<N, D>),
+}
+
+impl<W: Write + Seek> Builder<W, WantsQuestions> {
+    pub fn write_zone(self, zone: &str) -> Result<Self, Error> {
+        self.write_question(&Question {
+            name: zone,
+            typ: Type::SOA.into(),
+            class: Class::INET.into(),
+        })
+    }
+}
+
+impl<W: Write + Seek> Builder<W, WantsAnswers> {
+    pub fn write_prerequisite<N, D>(self, prerequisite: &Prerequisite<N, D>) -> Result<Self, Error>
+    where
+        N: AsRef<str> + Clone,
+        D: AsRef<[u8]> + Clone,
+    {
+        match prerequisite {
+            Prerequisite::RRsetExists { name, typ } => self.write_raw_record(name.as_ref(), *typ, Class::ANY.into(), 0, &[]),
+            Prerequisite::RRsetMatches(resource) => self.write_answer(&Resource {
+                ttl: 0,
+                ..resource.clone()
+            }),
+            Prerequisite::RRsetNotExists { name, typ } => self.write_raw_record(name.as_ref(), *typ, Class::NONE.into(), 0, &[]),
+            Prerequisite::NameInUse(name) => self.write_raw_record(name.as_ref(), Type::ALL.into(), Class::ANY.into(), 0, &[]),
+            Prerequisite::NameNotInUse(name) => {
+                self.write_raw_record(name.as_ref(), Type::ALL.into(), Class::NONE.into(), 0, &[])
+            }
+        }
+    }
+}
+
+impl<W: Write + Seek> Builder<W, WantsAuthorities> {
+    pub fn write_update<N, D>(self, update: &Update<N, D>) -> Result<Self, Error>
+    where
+        N: AsRef<str> + Clone,
+        D: AsRef<[u8]> + Clone,
+    {
+        match update {
+            Update::Add(resource) => self.write_authority(resource),
+            Update::DeleteRRset { name, typ } => self.write_raw_record(name.as_ref(), *typ, Class::ANY.into(), 0, &[]),
+            Update::DeleteName(name) => self.write_raw_record(name.as_ref(), Type::ALL.into(), Class::ANY.into(), 0, &[]),
+            Update::Delete(resource) => self.write_authority(&Resource {
+                class: Class::NONE.into(),
+                ttl: 0,
+                ..resource.clone()
+            }),
+        }
+    }
+}
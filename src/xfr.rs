@@ -0,0 +1,72 @@
+use crate::{Error, NameVisitor, Packet, Resource, ResourceData};
+
+// RFC 1982 sequence space, serials exactly half the space apart can't be ordered
+pub fn serial_newer(a: u32, b: u32) -> Option<bool> {
+    match a.wrapping_sub(b) {
+        0x8000_0000 => None,
+        distance => Some(distance != 0 && distance < 0x8000_0000),
+    }
+}
+
+pub struct XfrReader<B> {
+    packets: Vec<Packet<B>>,
+    serial: Option<u32>,
+    complete: bool,
+}
+
+impl<B> Default for XfrReader<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> XfrReader<B> {
+    pub fn new() -> Self {
+        Self {
+            packets: Vec::new(),
+            serial: None,
+            complete: false,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    pub fn into_packets(self) -> Vec<Packet<B>> {
+        self.packets
+    }
+}
+
+impl<B: AsRef<[u8]>> XfrReader<B> {
+    pub fn push(&mut self, packet: Packet<B>) -> Result<(), Error> {
+        let mut serial = self.serial;
+        let mut complete = self.complete;
+
+        for answer in packet.answers() {
+            let answer = answer?;
+            if complete {
+                return Err(Error::InvalidZoneTransfer);
+            }
+
+            match (answer.data, serial) {
+                (ResourceData::SOA { serial: opening, .. }, None) => serial = Some(opening),
+                (_, None) => return Err(Error::InvalidZoneTransfer),
+                // the transfer ends with the opening SOA repeated (RFC 5936 section 2.2)
+                (ResourceData::SOA { serial: closing, .. }, Some(opening)) if closing == opening => complete = true,
+                (ResourceData::SOA { .. }, Some(_)) => return Err(Error::InvalidZoneTransfer),
+                _ => {}
+            }
+        }
+
+        self.packets.push(packet);
+        self.serial = serial;
+        self.complete = complete;
+
+        Ok(())
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = Result<Resource<NameVisitor<'_>, &'_ [u8]>, Error>> + '_ {
+        self.packets.iter().flat_map(|packet| packet.answers())
+    }
+}
@@ -1,11 +1,11 @@
 use std::{
+    borrow::Cow,
     io::Cursor,
     net::{Ipv4Addr, Ipv6Addr},
 };
 
-#[test]
-fn test_build_packet() {
-    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+fn build_packet() -> Vec<u8> {
+    dnsmessage::Builder::new(Cursor::new(Vec::new()))
         .unwrap()
         .write_header(dnsmessage::Header {
             id: 114,
@@ -138,7 +138,12 @@ fn test_build_packet() {
         .unwrap()
         .finish_additionals()
         .unwrap()
-        .into_inner();
+        .into_inner()
+}
+
+#[test]
+fn test_build_packet() {
+    let pkt = build_packet();
 
     let pkt = simple_dns::Packet::parse(&pkt).unwrap();
     assert_eq!(pkt.id(), 114);
@@ -264,3 +269,1110 @@ fn test_build_packet() {
         })
     );
 }
+
+#[test]
+fn test_build_strict() {
+    let build = |strict: bool, data: &[u8]| {
+        let builder = dnsmessage::Builder::new(Cursor::new(Vec::new())).unwrap();
+        let builder = if strict { builder.strict() } else { builder };
+
+        builder
+            .write_header(dnsmessage::Header {
+                id: 514,
+                resp: true,
+                opcode: 0,
+                rcode: dnsmessage::RCode::Success.into(),
+                flags: dnsmessage::HeaderFlags::empty(),
+            })?
+            .finish_questions()?
+            .write_answer(&dnsmessage::Resource::<&str, _> {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 255,
+                data: dnsmessage::ResourceData::Unknown {
+                    typ: dnsmessage::Type::A.into(),
+                    data,
+                },
+            })?
+            .finish_answers()?
+            .finish_authorities()?
+            .finish_additionals()
+    };
+
+    assert!(build(false, &[1u8; 16]).is_ok());
+    assert!(build(true, &[1u8; 4]).is_ok());
+    assert!(matches!(build(true, &[1u8; 16]), Err(dnsmessage::Error::InvalidRecordData)));
+}
+
+#[test]
+fn test_build_raw_record() {
+    let spf = b"\x0ev=spf1 mx ~all";
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 1919,
+            resp: true,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::empty(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_raw_record(
+            "example.org.",
+            dnsmessage::Type::SPF.into(),
+            dnsmessage::Class::INET.into(),
+            3600,
+            spf,
+        )
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.answers_len(), 1);
+
+    let answer = pkt.answers().next().unwrap().unwrap();
+    assert_eq!(TryInto::<String>::try_into(answer.name).unwrap(), "example.org.");
+    assert_eq!(answer.class, dnsmessage::Class::INET.into());
+    assert_eq!(answer.ttl, 3600);
+    assert_eq!(answer.data, dnsmessage::ResourceData::SPF { txt: vec![&spf[1..]] });
+}
+
+#[test]
+fn test_build_copy_visitors() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 8080,
+            resp: true,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_AVAILABLE,
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::CNAME { cname: "example.org." },
+        })
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::MX {
+                preference: 10,
+                mx: "mx.example.org.",
+            },
+        })
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<&str, &[u8]> {
+            name: "example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::TXT {
+                txt: vec![b"hello", b"world"],
+            },
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(pkt).unwrap();
+
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(pkt.header().unwrap())
+        .unwrap()
+        .finish_questions()
+        .unwrap();
+    for answer in pkt.answers() {
+        builder = builder.write_answer_visitor(&answer.unwrap()).unwrap();
+    }
+    let copied = builder
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let copied = dnsmessage::Packet::new(copied).unwrap();
+
+    assert_eq!(copied.answers_len(), pkt.answers_len());
+    for (original, copied) in pkt.answers().zip(copied.answers()) {
+        assert_eq!(
+            original.unwrap().try_into_owned::<String, Vec<u8>>().unwrap(),
+            copied.unwrap().try_into_owned::<String, Vec<u8>>().unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_build_copy_escaped_labels() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 8080,
+            resp: true,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_AVAILABLE,
+        })
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "a\\\\b.org.",
+            typ: dnsmessage::Type::CNAME.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "a\\\\b.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::CNAME { cname: "x\\.y.org." },
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(pkt).unwrap();
+
+    let labels = |name: dnsmessage::NameVisitor| name.segments().map(|label| label.unwrap().to_vec()).collect::<Vec<_>>();
+
+    let copied = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(pkt.header().unwrap())
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer_visitor(&pkt.answers().next().unwrap().unwrap())
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let copied = dnsmessage::Packet::new(copied).unwrap();
+
+    // the backslash and the dot stay inside their labels
+    let answer = copied.answers().next().unwrap().unwrap();
+    assert_eq!(labels(answer.name), vec![b"a\\b".to_vec(), b"org".to_vec()]);
+    let dnsmessage::ResourceData::CNAME { cname } = answer.data else {
+        panic!("unexpected data: {:?}", answer.data);
+    };
+    assert_eq!(labels(cname), vec![b"x.y".to_vec(), b"org".to_vec()]);
+
+    let response = dnsmessage::Builder::error_response(&pkt, dnsmessage::RCode::Refused, false).unwrap();
+    let response = dnsmessage::Packet::new(&response[..]).unwrap();
+    let question = response.questions().next().unwrap().unwrap();
+    assert_eq!(labels(question.name), vec![b"a\\b".to_vec(), b"org".to_vec()]);
+}
+
+#[test]
+fn test_build_any_query() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 255,
+            resp: false,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_DESIRED,
+        })
+        .unwrap()
+        .write_any_question("example.org.")
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(&pkt[12..], b"\x07example\x03org\x00\x00\xff\x00\x01");
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.is_any_query().unwrap());
+
+    let question = pkt.questions().next().unwrap().unwrap();
+    assert!(question.is_any());
+    assert_eq!(question.typ, dnsmessage::Type::ALL.into());
+    assert_eq!(question.class, dnsmessage::Class::INET.into());
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(255))
+        .unwrap()
+        .write_question(&dnsmessage::Question::any("example.org."))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let question = pkt.single_question().unwrap();
+    assert_eq!(TryInto::<String>::try_into(&question.name).unwrap(), "example.org.");
+    assert_eq!(question.typ, dnsmessage::Type::ALL.into());
+    assert_eq!(question.class, dnsmessage::Class::INET.into());
+}
+
+#[test]
+fn test_build_root_query() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(1))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: ".",
+            typ: dnsmessage::Type::NS.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(&pkt[12..], b"\x00\x00\x02\x00\x01");
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let question = pkt.single_question().unwrap();
+    assert_eq!(question.name.label_count().unwrap(), 0);
+    assert_eq!(TryInto::<String>::try_into(&question.name).unwrap(), ".");
+    assert!(
+        question
+            .matches(".", dnsmessage::Type::NS.into(), dnsmessage::Class::INET.into())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_build_dnssec_header_flags() {
+    let build = |header: dnsmessage::Header| {
+        dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(header)
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+            .into_inner()
+    };
+
+    let flags = dnsmessage::HeaderFlags::RECURSION_DESIRED
+        | dnsmessage::HeaderFlags::AUTHENTIC_DATA
+        | dnsmessage::HeaderFlags::CHECKING_DISABLED;
+    let header = dnsmessage::Header::query(53).with_flags(flags);
+
+    let pkt = build(header);
+    assert_eq!(&pkt[2..4], &[0x01, 0x30]);
+    assert_eq!(dnsmessage::Packet::new(&pkt[..]).unwrap().header().unwrap(), header);
+
+    // the full four-bit opcode must not spill into or be cut by the flag bits
+    let header = dnsmessage::Header {
+        opcode: 0b1000,
+        ..dnsmessage::Header::response(53, dnsmessage::RCode::Success.into()).with_flags(flags)
+    };
+    let mut pkt = dnsmessage::Packet::new(build(header)).unwrap();
+    assert_eq!(pkt.header().unwrap(), header);
+
+    pkt.set_flag(dnsmessage::HeaderFlags::CHECKING_DISABLED, false);
+    let header = pkt.header().unwrap();
+    assert_eq!(header.opcode, 0b1000);
+    assert_eq!(
+        header.flags,
+        dnsmessage::HeaderFlags::RECURSION_DESIRED | dnsmessage::HeaderFlags::AUTHENTIC_DATA
+    );
+}
+
+#[test]
+fn test_build_reserved_z_bit() {
+    let pkt = build_packet();
+    assert_eq!(pkt[3] & 0x40, 0);
+
+    let mut pkt = dnsmessage::Packet::new(pkt).unwrap();
+    assert!(!pkt.header().unwrap().flags.contains(dnsmessage::HeaderFlags::Z));
+
+    pkt.set_flag(dnsmessage::HeaderFlags::Z, true);
+    assert!(pkt.header().unwrap().flags.contains(dnsmessage::HeaderFlags::Z));
+    assert_eq!(pkt[3] & 0x40, 0x40);
+}
+
+#[test]
+fn test_header_constructors() {
+    assert_eq!(
+        dnsmessage::Header::query(1234),
+        dnsmessage::Header {
+            id: 1234,
+            resp: false,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::empty(),
+        }
+    );
+    assert_eq!(
+        dnsmessage::Header::response(1234, dnsmessage::RCode::NameError.into())
+            .with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED | dnsmessage::HeaderFlags::RECURSION_AVAILABLE),
+        dnsmessage::Header {
+            id: 1234,
+            resp: true,
+            opcode: 0,
+            rcode: dnsmessage::RCode::NameError.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_DESIRED | dnsmessage::HeaderFlags::RECURSION_AVAILABLE,
+        }
+    );
+}
+
+#[test]
+fn test_all_records() {
+    let pkt = build_packet();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let sections = pkt
+        .all_records()
+        .map(|record| record.map(|(section, _)| section))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(sections.len(), 11);
+    assert_eq!(
+        sections
+            .iter()
+            .filter(|section| **section == dnsmessage::Section::Answer)
+            .count(),
+        4
+    );
+    assert_eq!(
+        sections
+            .iter()
+            .filter(|section| **section == dnsmessage::Section::Authority)
+            .count(),
+        1
+    );
+    assert_eq!(
+        sections
+            .iter()
+            .filter(|section| **section == dnsmessage::Section::Additional)
+            .count(),
+        6
+    );
+}
+
+#[test]
+fn test_peek_header() {
+    let pkt = build_packet();
+
+    let header = dnsmessage::peek_header(&pkt).unwrap();
+    assert_eq!(header, dnsmessage::Packet::new(&pkt[..]).unwrap().header().unwrap());
+    assert!(matches!(
+        dnsmessage::peek_header(&pkt[..11]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+}
+
+#[test]
+fn test_build_name_too_long() {
+    let build = |name: &str| {
+        dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::query(1234))
+            .unwrap()
+            .write_question(&dnsmessage::Question {
+                name,
+                typ: dnsmessage::Type::A.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .map(|_| ())
+    };
+
+    let name = "abcdefghi.".repeat(25) + "abc.";
+    assert_eq!(name.len() + 1, 255);
+    assert!(build(&name).is_ok());
+
+    let name = "abcdefghi.".repeat(25) + "abcd.";
+    assert!(matches!(build(&name), Err(dnsmessage::Error::NameTooLong)));
+}
+
+#[test]
+fn test_set_flags() {
+    let mut pkt = dnsmessage::Packet::new(build_packet()).unwrap();
+    let header = pkt.header().unwrap();
+    assert!(!pkt.is_truncated());
+
+    pkt.set_flag(dnsmessage::HeaderFlags::TRUNCATED, true);
+    assert!(pkt.is_truncated());
+    assert_eq!(
+        pkt.header().unwrap(),
+        dnsmessage::Header {
+            flags: header.flags | dnsmessage::HeaderFlags::TRUNCATED,
+            ..header
+        }
+    );
+
+    pkt.set_flag(dnsmessage::HeaderFlags::TRUNCATED, false);
+    assert_eq!(pkt.header().unwrap(), header);
+
+    pkt.set_flags(dnsmessage::HeaderFlags::AUTHORITATIVE);
+    pkt.set_rcode(dnsmessage::RCode::Refused.into());
+    assert_eq!(
+        pkt.header().unwrap(),
+        dnsmessage::Header {
+            flags: dnsmessage::HeaderFlags::AUTHORITATIVE,
+            rcode: dnsmessage::RCode::Refused.into(),
+            ..header
+        }
+    );
+}
+
+#[test]
+fn test_lazy_packet() {
+    let pkt = build_packet();
+    let eager = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let lazy = dnsmessage::LazyPacket::new(&pkt[..]).unwrap();
+
+    assert_eq!(lazy.header().unwrap(), eager.header().unwrap());
+    assert_eq!(
+        lazy.questions().collect::<Result<Vec<_>, _>>().unwrap(),
+        eager.questions().collect::<Result<Vec<_>, _>>().unwrap()
+    );
+    assert_eq!(
+        lazy.additionals().unwrap().collect::<Result<Vec<_>, _>>().unwrap(),
+        eager.additionals().collect::<Result<Vec<_>, _>>().unwrap()
+    );
+    assert_eq!(
+        lazy.answers().unwrap().collect::<Result<Vec<_>, _>>().unwrap(),
+        eager.answers().collect::<Result<Vec<_>, _>>().unwrap()
+    );
+
+    // only the header and questions are read, so the truncated answers go unnoticed
+    let lazy = dnsmessage::LazyPacket::new(&pkt[..pkt.len() - 1]).unwrap();
+    for (lazy, eager) in lazy.questions().zip(eager.questions()) {
+        assert_eq!(
+            lazy.unwrap().try_into_owned::<String>().unwrap(),
+            eager.unwrap().try_into_owned::<String>().unwrap()
+        );
+    }
+    assert!(dnsmessage::Packet::new(&pkt[..pkt.len() - 1]).is_err());
+}
+
+#[test]
+fn test_type_histogram() {
+    let pkt = build_packet();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let histogram = pkt.type_histogram().unwrap();
+    assert_eq!(histogram.values().sum::<usize>(), 11);
+    assert_eq!(histogram[&dnsmessage::Type::AAAA.into()], 3);
+    assert_eq!(histogram[&dnsmessage::Type::CNAME.into()], 1);
+    assert_eq!(histogram[&dnsmessage::Type::NS.into()], 1);
+}
+
+#[test]
+fn test_build_strict_names() {
+    let build = |strict_names: Option<bool>, name: &str| {
+        let builder = dnsmessage::Builder::new(Cursor::new(Vec::new())).unwrap();
+        let builder = match strict_names {
+            Some(allow_underscore) => builder.strict_names(allow_underscore),
+            None => builder,
+        };
+
+        builder
+            .write_header(dnsmessage::Header::query(515))?
+            .write_question(&dnsmessage::Question {
+                name,
+                typ: dnsmessage::Type::TXT.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .map(|_| ())
+    };
+
+    assert!(build(Some(true), "_dmarc.example.org.").is_ok());
+    assert!(matches!(
+        build(Some(false), "_dmarc.example.org."),
+        Err(dnsmessage::Error::InvalidNameSegmentBody)
+    ));
+    assert!(build(Some(false), "mail-1.example.org.").is_ok());
+    assert!(build(None, "my host.example.org.").is_ok());
+    assert!(matches!(
+        build(Some(true), "my host.example.org."),
+        Err(dnsmessage::Error::InvalidNameSegmentBody)
+    ));
+}
+
+#[test]
+fn test_build_from_cow() {
+    let pkt = build_packet();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let owned = |section: &mut dyn Iterator<Item = Result<dnsmessage::Resource<dnsmessage::NameVisitor, &[u8]>, _>>| {
+        section
+            .map(|record| record.unwrap().try_into_owned::<Cow<str>, Cow<[u8]>>().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let answers = owned(&mut pkt.answers());
+    let authorities = owned(&mut pkt.authorities());
+    let additionals = owned(&mut pkt.additionals());
+
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(pkt.header().unwrap())
+        .unwrap();
+    for question in pkt.questions() {
+        builder = builder
+            .write_question(&question.unwrap().try_into_owned::<Cow<str>>().unwrap())
+            .unwrap();
+    }
+    let mut builder = builder.finish_questions().unwrap();
+    for answer in &answers {
+        builder = builder.write_answer(answer).unwrap();
+    }
+    let mut builder = builder.finish_answers().unwrap();
+    for authority in &authorities {
+        builder = builder.write_authority(authority).unwrap();
+    }
+    let mut builder = builder.finish_authorities().unwrap();
+    for additional in &additionals {
+        builder = builder.write_additional(additional).unwrap();
+    }
+    let rebuilt = builder.finish_additionals().unwrap().into_inner();
+
+    assert_eq!(&rebuilt[..], &pkt[..]);
+}
+
+#[test]
+fn test_compression_stats() {
+    let txt = [&[b'x'; 255][..]; 64];
+
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(16384, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "example.org.",
+            typ: dnsmessage::Type::TXT.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource {
+            name: "example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::TXT { txt: txt.to_vec() },
+        })
+        .unwrap();
+    assert_eq!(
+        builder.compression_stats(),
+        dnsmessage::CompressionStats {
+            compressed: 1,
+            beyond_pointer_range: 0,
+        }
+    );
+
+    for name in ["a.example.org.", "b.example.net.", "b.example.net."] {
+        builder = builder
+            .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                name,
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::A { a: Ipv4Addr::LOCALHOST },
+            })
+            .unwrap();
+    }
+    assert_eq!(
+        builder.compression_stats(),
+        dnsmessage::CompressionStats {
+            compressed: 2,
+            beyond_pointer_range: 3,
+        }
+    );
+
+    let pkt = builder
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    assert!(pkt.len() > 16 * 1024);
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let names = pkt
+        .answers()
+        .map(|answer| TryInto::<String>::try_into(answer.unwrap().name).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["example.org.", "a.example.org.", "b.example.net.", "b.example.net."]);
+}
+
+#[test]
+fn test_error_response() {
+    let query = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(4242).with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "nonexistent.example.org.",
+            typ: dnsmessage::Type::AAAA.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let query = dnsmessage::Packet::new(query).unwrap();
+
+    let pkt = dnsmessage::Builder::error_response(&query, dnsmessage::RCode::NameError, false).unwrap();
+    let pkt = dnsmessage::Packet::new(pkt).unwrap();
+    assert_eq!(
+        pkt.header().unwrap(),
+        dnsmessage::Header::response(4242, dnsmessage::RCode::NameError.into())
+            .with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED)
+    );
+
+    let recursive = dnsmessage::Builder::error_response(&query, dnsmessage::RCode::NameError, true).unwrap();
+    assert_eq!(
+        dnsmessage::Packet::new(recursive).unwrap().header().unwrap(),
+        dnsmessage::Header::response(4242, dnsmessage::RCode::NameError.into())
+            .with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED | dnsmessage::HeaderFlags::RECURSION_AVAILABLE)
+    );
+    assert_eq!(
+        pkt.single_question().unwrap().try_into_owned::<String>().unwrap(),
+        dnsmessage::Question {
+            name: "nonexistent.example.org.".to_string(),
+            typ: dnsmessage::Type::AAAA.into(),
+            class: dnsmessage::Class::INET.into(),
+        }
+    );
+    assert_eq!(pkt.answers_len(), 0);
+    assert_eq!(pkt.authorities_len(), 0);
+    assert_eq!(pkt.additionals_len(), 0);
+}
+
+#[test]
+fn test_clone_packet() {
+    let pkt = dnsmessage::Packet::new(build_packet()).unwrap();
+    let cloned = pkt.clone();
+    drop(pkt);
+
+    let pkt = dnsmessage::Packet::new(build_packet()).unwrap();
+    assert_eq!(
+        cloned.questions().collect::<Result<Vec<_>, _>>().unwrap(),
+        pkt.questions().collect::<Result<Vec<_>, _>>().unwrap()
+    );
+    assert_eq!(cloned, pkt);
+}
+
+#[test]
+fn test_section_bytes() {
+    let pkt = build_packet();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    assert_eq!(
+        12 + pkt.questions_bytes().len()
+            + pkt.answers_bytes().len()
+            + pkt.authorities_bytes().len()
+            + pkt.additionals_bytes().len(),
+        pkt.len()
+    );
+
+    // owner (1) + type, class, ttl, rdlen (10) + address (4)
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(3072, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: ".",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::A { a: Ipv4Addr::LOCALHOST },
+        })
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: ".",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::A { a: Ipv4Addr::BROADCAST },
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.questions_bytes(), b"");
+    assert_eq!(pkt.answers_bytes().len(), 2 * (1 + 10 + 4));
+    assert_eq!(&pkt.answers_bytes()[11..15], &[127, 0, 0, 1]);
+    assert_eq!(pkt.authorities_bytes(), b"");
+    assert_eq!(pkt.additionals_bytes(), b"");
+}
+
+#[test]
+fn test_finish_additionals_with_len() {
+    // a pre-sized buffer with room reserved for a TCP length prefix
+    let mut cursor = Cursor::new(vec![0u8; 512]);
+    cursor.set_position(2);
+
+    let (cursor, len) = dnsmessage::Builder::new(cursor)
+        .unwrap()
+        .write_header(dnsmessage::Header::query(2201))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap()
+        .finish_additionals_with_len()
+        .unwrap();
+
+    assert_eq!(cursor.position(), len + 2);
+
+    let buf = cursor.into_inner();
+    assert_eq!(buf.len(), 512);
+
+    let pkt = dnsmessage::Packet::new(&buf[2..2 + len as usize]).unwrap();
+    assert_eq!(pkt.header().unwrap().id, 2201);
+    assert_eq!(pkt.as_ref().len() as u64, len);
+    assert!(dnsmessage::Packet::new(&buf[2..]).is_err());
+}
+
+#[test]
+fn test_slice_builder() {
+    let build = |buf: &mut [u8]| -> Result<usize, dnsmessage::Error> {
+        let writer = dnsmessage::SliceBuilder::new(dnsmessage::SliceWriter::new(buf))?
+            .write_header(dnsmessage::Header::query(4321).with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED))?
+            .write_question(&dnsmessage::Question {
+                name: "www.example.org.",
+                typ: dnsmessage::Type::AAAA.into(),
+                class: dnsmessage::Class::INET.into(),
+            })?
+            .finish_questions()?
+            .finish_answers()?
+            .finish_authorities()?
+            .enable_dnssec(1232)?
+            .finish_additionals()?;
+
+        Ok(writer.position())
+    };
+
+    let mut buf = [0u8; 512];
+    let len = build(&mut buf).unwrap();
+
+    let pkt = dnsmessage::Packet::new(&buf[..len]).unwrap();
+    assert_eq!(pkt.header().unwrap().id, 4321);
+    assert!(
+        pkt.single_question()
+            .unwrap()
+            .matches(
+                "www.example.org.",
+                dnsmessage::Type::AAAA.into(),
+                dnsmessage::Class::INET.into()
+            )
+            .unwrap()
+    );
+    assert_eq!(pkt.edns().unwrap().unwrap().udp_payload_size, 1232);
+
+    let mut buf = [0u8; 512];
+    for short in [8, len - 1] {
+        assert!(matches!(build(&mut buf[..short]), Err(dnsmessage::Error::ShortBuffer)));
+    }
+
+    // other writers keep reporting their own io errors
+    let header = dnsmessage::Builder::new(Cursor::new(&mut buf[..8]))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(4321));
+    assert!(matches!(header, Err(dnsmessage::Error::IoError(_))));
+}
+
+#[test]
+fn test_should_retry_tcp() {
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .with_max_size(512)
+        .write_header(dnsmessage::Header::response(3301, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::TXT.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap();
+    for _ in 0..4 {
+        builder = builder
+            .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::TXT { txt: vec![&[b'x'; 200]] },
+            })
+            .unwrap();
+    }
+    let pkt = builder
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.should_retry_tcp());
+    assert_eq!(pkt.answers().count(), 2);
+
+    let pkt = dnsmessage::Packet::new(build_packet()).unwrap();
+    assert!(!pkt.should_retry_tcp());
+}
+
+#[test]
+fn test_build_query() {
+    let pkt = dnsmessage::Builder::query_with_id(
+        0x1234,
+        "www.example.org.",
+        dnsmessage::Type::AAAA.into(),
+        dnsmessage::Class::INET.into(),
+    )
+    .unwrap();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let header = pkt.header().unwrap();
+    assert_eq!(header.id, 0x1234);
+    assert!(!header.resp);
+    assert_eq!(header.flags, dnsmessage::HeaderFlags::RECURSION_DESIRED);
+
+    let question = pkt.single_question().unwrap();
+    assert_eq!(TryInto::<String>::try_into(question.name).unwrap(), "www.example.org.");
+    assert_eq!(question.typ, dnsmessage::Type::AAAA.into());
+
+    let mut ids = [0xbeef, 0xcafe].into_iter();
+    for expected in [0xbeef, 0xcafe] {
+        let pkt = dnsmessage::Builder::query(
+            || ids.next().unwrap(),
+            "www.example.org.",
+            dnsmessage::Type::A.into(),
+            dnsmessage::Class::INET.into(),
+        )
+        .unwrap();
+        let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+        assert_eq!(pkt.header().unwrap().id, expected);
+        assert_eq!(pkt.questions().count(), 1);
+    }
+}
+
+#[test]
+fn test_header_encode() {
+    let header = dnsmessage::Header {
+        id: 0xbeef,
+        resp: true,
+        opcode: 5,
+        rcode: dnsmessage::RCode::Refused.into(),
+        flags: dnsmessage::HeaderFlags::AUTHORITATIVE | dnsmessage::HeaderFlags::RECURSION_DESIRED,
+    };
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(header)
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(header.encode(), pkt[..12]);
+    assert_eq!(dnsmessage::peek_header(&header.encode()).unwrap(), header);
+}
+
+#[test]
+fn test_build_raw_rdata_override() {
+    let build = |rdlen_override: Option<u16>| {
+        dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::response(4415, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .write_raw_rdata(
+                "www.example.org.",
+                dnsmessage::Type::A.into(),
+                dnsmessage::Class::INET.into(),
+                300,
+                rdlen_override,
+                &[127, 0, 0, 1],
+            )
+            .unwrap()
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+            .into_inner()
+    };
+
+    let pkt = build(None);
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(
+        pkt.answers().next().unwrap().unwrap().data,
+        dnsmessage::ResourceData::A { a: Ipv4Addr::LOCALHOST }
+    );
+
+    let pkt = build(Some(16));
+    assert_eq!(&pkt[pkt.len() - 6..pkt.len() - 4], &[0, 16]);
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+}
+
+#[test]
+fn test_reverse_question() {
+    let question = dnsmessage::Question::reverse(Ipv4Addr::new(1, 2, 3, 4).into());
+    assert_eq!(question.name, "4.3.2.1.in-addr.arpa.");
+    assert_eq!(question.typ, dnsmessage::Type::PTR.into());
+    assert_eq!(question.class, dnsmessage::Class::INET.into());
+
+    let question = dnsmessage::Question::reverse("2001:db8::567:89ab".parse::<Ipv6Addr>().unwrap().into());
+    assert_eq!(
+        question.name,
+        "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+    );
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(6626))
+        .unwrap()
+        .write_question(&question)
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.single_question().unwrap().try_into_owned::<String>().unwrap(), question);
+}
+
+#[test]
+fn test_build_back_to_back() {
+    let write = |writer: Cursor<Vec<u8>>, id: u16, name: &str| {
+        dnsmessage::Builder::new(writer)
+            .unwrap()
+            .write_header(dnsmessage::Header::response(id, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .write_question(&dnsmessage::Question {
+                name,
+                typ: dnsmessage::Type::A.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                name,
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::A { a: Ipv4Addr::LOCALHOST },
+            })
+            .unwrap()
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+    };
+
+    let writer = write(Cursor::new(Vec::new()), 1, "www.example.org.");
+    let first_len = writer.get_ref().len();
+    let buf = write(writer, 2, "www.example.org.").into_inner();
+
+    let (first, second) = buf.split_at(first_len);
+    assert_eq!(first.len(), second.len());
+
+    for (pkt, id) in [(first, 1), (second, 2)] {
+        // the answer owner points back to the question name at offset 12 of its own message
+        assert_eq!(&pkt[33..35], &[0xc0, 0x0c]);
+
+        let pkt = dnsmessage::Packet::new(pkt).unwrap();
+        assert_eq!(pkt.header().unwrap().id, id);
+
+        let answer = pkt.answers().next().unwrap().unwrap();
+        assert_eq!(TryInto::<String>::try_into(answer.name).unwrap(), "www.example.org.");
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::Cursor;
+
+#[test]
+fn test_chain() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 4399,
+            resp: false,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_DESIRED | dnsmessage::HeaderFlags::CHECKING_DISABLED,
+        })
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let header = dnsmessage::Packet::new(&pkt[..]).unwrap().header().unwrap();
+
+    let (first, second) = pkt.split_at(3);
+    let chain = dnsmessage::Chain::new(first, second);
+    assert_eq!(dnsmessage::Octets::len(&chain), pkt.len());
+    assert_eq!(dnsmessage::Header::parse(&chain).unwrap(), header);
+    assert_eq!(header.id, 4399);
+
+    let (first, second) = pkt.split_at(17);
+    let chain = dnsmessage::Chain::new(first, second);
+    let name = dnsmessage::NameVisitor::new(&chain, 12);
+    assert_eq!(TryInto::<String>::try_into(name.clone()).unwrap(), "www.example.org.");
+    assert_eq!(
+        name.octet_segments().collect::<Result<Vec<_>, _>>().unwrap(),
+        vec![&b"www"[..], &b"example"[..], &b"org"[..]]
+    );
+
+    // contiguous buffers keep lending plain slices
+    let name = dnsmessage::NameVisitor::new(&pkt[..], 12);
+    let labels: Vec<&[u8]> = name.segments().collect::<Result<_, _>>().unwrap();
+    assert_eq!(labels, vec![&b"www"[..], &b"example"[..], &b"org"[..]]);
+
+    let (first, second) = pkt.split_at(10);
+    let chain = dnsmessage::Chain::new(first, second);
+    assert!(dnsmessage::Header::parse(&dnsmessage::Chain::new(&first[..3], &[])).is_err());
+    assert_eq!(dnsmessage::Header::parse(&chain).unwrap(), header);
+}
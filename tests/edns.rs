@@ -0,0 +1,645 @@
+use std::io::Cursor;
+
+fn build_with_additionals(additionals: &[dnsmessage::Resource<&str, &[u8]>]) -> Result<Vec<u8>, dnsmessage::Error> {
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))?
+        .write_header(dnsmessage::Header {
+            id: 2333,
+            resp: false,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_DESIRED,
+        })?
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })?
+        .finish_questions()?
+        .finish_answers()?
+        .finish_authorities()?;
+
+    for additional in additionals {
+        builder = builder.write_additional(additional)?;
+    }
+
+    Ok(builder.finish_additionals()?.into_inner())
+}
+
+// the builder refuses a second OPT, so this one is appended by hand
+fn append_opt(mut pkt: Vec<u8>) -> Vec<u8> {
+    pkt.extend_from_slice(&[0x00, 0x00, 0x29, 0x04, 0xd0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    pkt[11] += 1;
+    pkt
+}
+
+fn build_with_options(options: Vec<dnsmessage::EdnsOption<&[u8]>>) -> Result<Vec<u8>, dnsmessage::Error> {
+    build_with_additionals(&[dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(1232),
+        ttl: 0,
+        data: dnsmessage::ResourceData::OPT { options },
+    }])
+}
+
+fn assert_options(pkt: &[u8], expected: Vec<dnsmessage::EdnsOption<&[u8]>>) {
+    let pkt = dnsmessage::Packet::new(pkt).unwrap();
+    let mut additionals = pkt.additionals();
+    let additional = additionals.next().unwrap().unwrap();
+    assert!(additionals.next().is_none());
+
+    assert_eq!(TryInto::<String>::try_into(additional.name).unwrap(), ".");
+    assert_eq!(additional.data, dnsmessage::ResourceData::OPT { options: expected });
+}
+
+#[test]
+fn test_cookie() {
+    let client = [1u8, 2, 3, 4, 5, 6, 7, 8];
+
+    let pkt = build_with_options(vec![dnsmessage::EdnsOption::Cookie { client, server: None }]).unwrap();
+    assert_options(&pkt, vec![dnsmessage::EdnsOption::Cookie { client, server: None }]);
+
+    let server = vec![9u8, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20];
+    let pkt = build_with_options(vec![dnsmessage::EdnsOption::Cookie {
+        client,
+        server: Some(server.clone()),
+    }])
+    .unwrap();
+    assert_options(
+        &pkt,
+        vec![dnsmessage::EdnsOption::Cookie {
+            client,
+            server: Some(server),
+        }],
+    );
+
+    let err = build_with_options(vec![dnsmessage::EdnsOption::Cookie {
+        client,
+        server: Some(vec![0u8; 4]),
+    }])
+    .unwrap_err();
+    assert!(matches!(err, dnsmessage::Error::InvalidEdnsOption));
+}
+
+#[test]
+fn test_nsid() {
+    let pkt = build_with_options(vec![dnsmessage::EdnsOption::nsid_request()]).unwrap();
+    assert_options(&pkt, vec![dnsmessage::EdnsOption::Nsid(Vec::new())]);
+
+    let pkt = build_with_options(vec![dnsmessage::EdnsOption::Nsid(b"ns1.example.org".to_vec())]).unwrap();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let additional = pkt.additionals().next().unwrap().unwrap();
+    let dnsmessage::ResourceData::OPT { options } = additional.data else {
+        panic!("unexpected data: {:?}", additional.data);
+    };
+    assert_eq!(options.iter().find_map(|option| option.nsid()), Some(&b"ns1.example.org"[..]));
+}
+
+#[test]
+fn test_extended_error() {
+    let pkt = build_with_options(vec![dnsmessage::EdnsOption::ExtendedError {
+        info_code: dnsmessage::ExtendedErrorCode::Prohibited.into(),
+        extra_text: b"blocked by policy",
+    }])
+    .unwrap();
+    assert_options(
+        &pkt,
+        vec![dnsmessage::EdnsOption::ExtendedError {
+            info_code: dnsmessage::ExtendedErrorCode::Prohibited.into(),
+            extra_text: b"blocked by policy",
+        }],
+    );
+
+    let pkt = build_with_options(vec![dnsmessage::EdnsOption::ExtendedError {
+        info_code: dnsmessage::MaybeUnknown::Unknown(49152),
+        extra_text: b"",
+    }])
+    .unwrap();
+    assert_options(
+        &pkt,
+        vec![dnsmessage::EdnsOption::ExtendedError {
+            info_code: dnsmessage::MaybeUnknown::Unknown(49152),
+            extra_text: b"",
+        }],
+    );
+}
+
+#[test]
+fn test_edns() {
+    let opt = dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(4096),
+        ttl: 0x0100_8000,
+        data: dnsmessage::ResourceData::OPT {
+            options: vec![dnsmessage::EdnsOption::nsid_request()],
+        },
+    };
+
+    let pkt = build_with_additionals(std::slice::from_ref(&opt)).unwrap();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(
+        pkt.edns().unwrap(),
+        Some(dnsmessage::Edns {
+            udp_payload_size: 4096,
+            ext_rcode: 1,
+            version: 0,
+            dnssec_ok: true,
+            options: vec![dnsmessage::EdnsOption::Nsid(Vec::new())],
+        })
+    );
+
+    let pkt = build_with_additionals(&[]).unwrap();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.edns().unwrap(), None);
+
+    let pkt = append_opt(build_with_additionals(&[opt]).unwrap());
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(matches!(pkt.edns(), Err(dnsmessage::Error::DuplicateOpt)));
+}
+
+#[test]
+fn test_dnssec_ok() {
+    let flags = dnsmessage::HeaderFlags::RECURSION_DESIRED
+        | dnsmessage::HeaderFlags::AUTHENTIC_DATA
+        | dnsmessage::HeaderFlags::CHECKING_DISABLED;
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(4034).with_flags(flags))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let mut pkt = dnsmessage::Packet::new(pkt).unwrap();
+    assert!(pkt.dnssec_ok().unwrap());
+    assert_eq!(pkt.edns().unwrap().unwrap().udp_payload_size, 1232);
+
+    let header = pkt.header().unwrap();
+    assert_eq!(header, dnsmessage::Header::query(4034).with_flags(flags));
+
+    pkt.set_header(dnsmessage::Header::response(4035, dnsmessage::RCode::Success.into()).with_flags(flags))
+        .unwrap();
+    assert_eq!(
+        pkt.header().unwrap(),
+        dnsmessage::Header::response(4035, dnsmessage::RCode::Success.into()).with_flags(flags)
+    );
+
+    let pkt = build_with_additionals(&[]).unwrap();
+    assert!(!dnsmessage::Packet::new(pkt).unwrap().dnssec_ok().unwrap());
+}
+
+#[test]
+fn test_write_opt() {
+    let client = [1u8, 2, 3, 4, 5, 6, 7, 8];
+    let options = [
+        dnsmessage::EdnsOption::Cookie { client, server: None },
+        dnsmessage::EdnsOption::Unknown {
+            code: dnsmessage::MaybeUnknown::Unknown(12),
+            data: &[0u8; 16][..],
+        },
+    ];
+
+    let builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(4034, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .write_opt(1232, 1, 0, true, &options)
+        .unwrap();
+    let pkt = builder.finish_additionals().unwrap().into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(
+        pkt.edns().unwrap(),
+        Some(dnsmessage::Edns {
+            udp_payload_size: 1232,
+            ext_rcode: 1,
+            version: 0,
+            dnssec_ok: true,
+            options: options.to_vec(),
+        })
+    );
+
+    let builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(4034))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap();
+    assert!(matches!(
+        builder.write_opt(1232, 0, 0, false, &options),
+        Err(dnsmessage::Error::DuplicateOpt)
+    ));
+}
+
+#[test]
+fn test_check_edns_constraints() {
+    let opt = dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(1232),
+        ttl: 0,
+        data: dnsmessage::ResourceData::OPT { options: Vec::new() },
+    };
+
+    let pkt = build_with_additionals(std::slice::from_ref(&opt)).unwrap();
+    assert!(dnsmessage::Packet::new(pkt).unwrap().check_edns_constraints().is_ok());
+
+    let pkt = append_opt(build_with_additionals(std::slice::from_ref(&opt)).unwrap());
+    assert!(matches!(
+        dnsmessage::Packet::new(pkt).unwrap().check_edns_constraints(),
+        Err(dnsmessage::Error::DuplicateOpt)
+    ));
+
+    let pkt = build_with_additionals(&[dnsmessage::Resource {
+        name: "example.org.",
+        ..opt.clone()
+    }])
+    .unwrap();
+    assert!(matches!(
+        dnsmessage::Packet::new(pkt).unwrap().check_edns_constraints(),
+        Err(dnsmessage::Error::InvalidOptOwner)
+    ));
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(2333, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&opt)
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    assert!(matches!(
+        dnsmessage::Packet::new(pkt).unwrap().check_edns_constraints(),
+        Err(dnsmessage::Error::MisplacedOpt)
+    ));
+}
+
+#[test]
+fn test_resume() {
+    let query = build_with_additionals(&[]).unwrap();
+    let pkt = dnsmessage::Packet::new(&query[..]).unwrap();
+    assert_eq!(pkt.edns().unwrap(), None);
+
+    let mut cursor = Cursor::new(query);
+    let builder = dnsmessage::Builder::resume(&mut cursor).unwrap().enable_dnssec(1232).unwrap();
+    builder.finish_additionals().unwrap();
+
+    let pkt = dnsmessage::Packet::new(cursor.into_inner()).unwrap();
+    assert_eq!(pkt.header().unwrap().id, 2333);
+    assert_eq!(pkt.questions().count(), 1);
+    assert_eq!(pkt.additionals().count(), 1);
+    assert!(pkt.dnssec_ok().unwrap());
+    assert_eq!(pkt.edns().unwrap().unwrap().udp_payload_size, 1232);
+
+    let mut cursor = Cursor::new(pkt.into_inner());
+    assert!(matches!(
+        dnsmessage::Builder::resume(&mut cursor).unwrap().enable_dnssec(1232),
+        Err(dnsmessage::Error::DuplicateOpt)
+    ));
+}
+
+#[test]
+fn test_max_size_for_query() {
+    let respond = |query: &[u8]| {
+        let query = dnsmessage::Packet::new(query).unwrap();
+        let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .with_max_size_for::<_, &[u8]>(&query, &[])
+            .unwrap()
+            .write_header(dnsmessage::Header::response(2333, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .write_question(&dnsmessage::Question {
+                name: "www.example.org.",
+                typ: dnsmessage::Type::A.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .unwrap()
+            .finish_questions()
+            .unwrap();
+        for idx in 0..40u8 {
+            builder = builder
+                .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                    name: "www.example.org.",
+                    class: dnsmessage::Class::INET.into(),
+                    ttl: 300,
+                    data: dnsmessage::ResourceData::A {
+                        a: std::net::Ipv4Addr::new(192, 0, 2, idx),
+                    },
+                })
+                .unwrap();
+        }
+
+        let builder = builder.finish_answers().unwrap().finish_authorities().unwrap();
+        let builder = if query.edns().unwrap().is_some() {
+            builder.enable_dnssec(1232).unwrap()
+        } else {
+            builder
+        };
+
+        builder.finish_additionals().unwrap().into_inner()
+    };
+
+    let opt = |udp_size: u16| dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(udp_size),
+        ttl: 0,
+        data: dnsmessage::ResourceData::OPT { options: Vec::new() },
+    };
+
+    let pkt = respond(&build_with_additionals(&[opt(4096)]).unwrap());
+    assert!(pkt.len() > 512);
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(!pkt.is_truncated());
+    assert_eq!(pkt.answers().count(), 40);
+
+    let pkt = respond(&build_with_additionals(&[]).unwrap());
+    assert!(pkt.len() <= 512);
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.is_truncated());
+    assert!(pkt.answers_len() < 40);
+    assert!(pkt.answers().all(|answer| answer.is_ok()));
+
+    // the room held back for the OPT keeps it in the truncated response
+    let pkt = respond(&build_with_additionals(&[opt(512)]).unwrap());
+    assert!(pkt.len() <= 512);
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.is_truncated());
+    assert!(pkt.answers_len() < 40);
+    assert!(pkt.dnssec_ok().unwrap());
+}
+
+#[test]
+fn test_max_size_opt_options() {
+    let client = [1u8, 2, 3, 4, 5, 6, 7, 8];
+    let cookie = || dnsmessage::EdnsOption::<&[u8]>::Cookie {
+        client,
+        server: Some(vec![9; 16]),
+    };
+    let respond = |builder: dnsmessage::Builder<Cursor<Vec<u8>>, dnsmessage::WantsHeader>| {
+        let mut builder = builder
+            .write_header(dnsmessage::Header::response(2333, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .finish_questions()
+            .unwrap();
+        for idx in 0..40u8 {
+            builder = builder
+                .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                    name: "www.example.org.",
+                    class: dnsmessage::Class::INET.into(),
+                    ttl: 300,
+                    data: dnsmessage::ResourceData::A {
+                        a: std::net::Ipv4Addr::new(192, 0, 2, idx),
+                    },
+                })
+                .unwrap();
+        }
+
+        let pkt = builder
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .write_opt(1232, 0, 0, false, &[cookie()])
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+            .into_inner();
+        assert!(pkt.len() <= 512);
+
+        let pkt = dnsmessage::Packet::new(pkt).unwrap();
+        assert!(pkt.is_truncated());
+        assert!(pkt.answers_len() < 40);
+        pkt.edns().unwrap().unwrap().options.len()
+    };
+
+    // the room held back covers the cookie echoed in the OPT, not just the bare record
+    let query = build_with_additionals(&[dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(512),
+        ttl: 0,
+        data: dnsmessage::ResourceData::OPT {
+            options: vec![dnsmessage::EdnsOption::Cookie { client, server: None }],
+        },
+    }])
+    .unwrap();
+    let query = dnsmessage::Packet::new(&query[..]).unwrap();
+    let builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .with_max_size_for(&query, &[cookie()])
+        .unwrap();
+    assert_eq!(respond(builder), 1);
+
+    // with room held back for a bare OPT only, the cookie is given up rather than failing the response
+    let builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .with_max_size_for::<_, &[u8]>(&query, &[])
+        .unwrap();
+    assert_eq!(respond(builder), 0);
+}
+
+#[test]
+fn test_max_size_overflow() {
+    let record = |name: &'static str, len: usize| dnsmessage::Resource::<_, Vec<u8>> {
+        name,
+        class: dnsmessage::Class::INET.into(),
+        ttl: 300,
+        data: dnsmessage::ResourceData::TXT {
+            txt: (0..len)
+                .step_by(100)
+                .map(|start| vec![b'x'; (len - start).min(100)])
+                .collect(),
+        },
+    };
+    let builder = || {
+        dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .with_max_size(512)
+            .write_header(dnsmessage::Header::response(2333, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+    };
+
+    // once a record is left out, smaller ones that would still fit are left out too, in every later section
+    let pkt = builder()
+        .write_answer(&record("a.example.org.", 200))
+        .unwrap()
+        .write_answer(&record("b.example.org.", 400))
+        .unwrap()
+        .write_answer(&record("c.example.org.", 10))
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .write_authority(&record("d.example.org.", 10))
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .write_additional(&record("e.example.org.", 10))
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.is_truncated());
+    assert_eq!(pkt.answers_len(), 1);
+    assert_eq!(pkt.authorities_len(), 0);
+    assert_eq!(pkt.additionals_len(), 0);
+
+    // additional data is left out without setting TC
+    let pkt = builder()
+        .write_answer(&record("a.example.org.", 200))
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .write_additional(&record("b.example.org.", 400))
+        .unwrap()
+        .write_additional(&record("c.example.org.", 10))
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(!pkt.is_truncated());
+    assert_eq!(pkt.answers_len(), 1);
+    assert_eq!(pkt.additionals_len(), 0);
+
+    // the OPT is still written after a drop, and fails loudly rather than going missing when it can't fit
+    let pkt = builder()
+        .write_answer(&record("a.example.org.", 400))
+        .unwrap()
+        .write_answer(&record("b.example.org.", 60))
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.is_truncated());
+    assert_eq!(pkt.answers_len(), 1);
+    assert!(pkt.dnssec_ok().unwrap());
+
+    let builder = builder()
+        .write_answer(&record("a.example.org.", 470))
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap();
+    assert!(matches!(builder.enable_dnssec(1232), Err(dnsmessage::Error::MessageTooLong)));
+}
+
+#[test]
+fn test_badvers() {
+    let query = build_with_additionals(&[dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(4096),
+        ttl: 1 << 16,
+        data: dnsmessage::ResourceData::OPT { options: Vec::new() },
+    }])
+    .unwrap();
+    let query = dnsmessage::Packet::new(&query[..]).unwrap();
+    assert_eq!(query.edns_version().unwrap(), Some(1));
+
+    let pkt = dnsmessage::Builder::badvers_response(&query, 1232).unwrap();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let header = pkt.header().unwrap();
+    assert_eq!(header.id, 2333);
+    assert!(header.resp);
+    assert_eq!(pkt.questions().count(), 1);
+
+    let edns = pkt.edns().unwrap().unwrap();
+    assert_eq!(edns.version, 0);
+    assert_eq!(edns.udp_payload_size, 1232);
+    assert_eq!(header.rcode, dnsmessage::RCode::Success.into());
+    assert_eq!((edns.ext_rcode as u16) << 4, dnsmessage::RCODE_BADVERS);
+
+    let pkt = build_with_additionals(&[]).unwrap();
+    assert_eq!(dnsmessage::Packet::new(&pkt[..]).unwrap().edns_version().unwrap(), None);
+}
+
+#[test]
+fn test_second_opt_rejected_on_every_path() {
+    let opt = dnsmessage::Resource {
+        name: ".",
+        class: dnsmessage::MaybeUnknown::Unknown(1232),
+        ttl: 0,
+        data: dnsmessage::ResourceData::OPT { options: Vec::new() },
+    };
+    let pkt = build_with_additionals(std::slice::from_ref(&opt)).unwrap();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let visitor = pkt.additionals().next().unwrap().unwrap();
+
+    let builder = || {
+        dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::query(4034))
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+    };
+
+    type Additionals = dnsmessage::Builder<Cursor<Vec<u8>>, dnsmessage::WantsAdditionals>;
+    let writes: [&dyn Fn(Additionals) -> Result<Additionals, dnsmessage::Error>; 5] = [
+        &|builder| builder.enable_dnssec(1232),
+        &|builder| builder.write_opt::<&[u8]>(1232, 0, 0, false, &[]),
+        &|builder| builder.write_additional(&opt),
+        &|builder| builder.write_additional_visitor(&visitor),
+        &|builder| builder.write_raw_record(".", dnsmessage::Type::OPT.into(), opt.class, 0, &[]),
+    ];
+
+    for first in writes {
+        for second in writes {
+            assert!(matches!(
+                second(first(builder()).unwrap()),
+                Err(dnsmessage::Error::DuplicateOpt)
+            ));
+        }
+    }
+}
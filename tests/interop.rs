@@ -0,0 +1,82 @@
+#![cfg(feature = "interop")]
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[test]
+fn test_interop_a() {
+    let record = simple_dns::ResourceRecord::new(
+        simple_dns::Name::new("www.example.org").unwrap(),
+        simple_dns::CLASS::IN,
+        255,
+        simple_dns::rdata::RData::A(simple_dns::rdata::A::from(Ipv4Addr::new(192, 0, 2, 1))),
+    );
+
+    let resource = dnsmessage::OwnedResource::try_from(record.clone()).unwrap();
+    assert_eq!(
+        resource,
+        dnsmessage::Resource {
+            name: "www.example.org.".to_string(),
+            class: dnsmessage::Class::INET.into(),
+            ttl: 255,
+            data: dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(192, 0, 2, 1),
+            },
+        }
+    );
+
+    assert_eq!(simple_dns::ResourceRecord::try_from(resource).unwrap(), record);
+}
+
+#[test]
+fn test_interop_txt() {
+    let resource = dnsmessage::Resource {
+        name: "example.org.".to_string(),
+        class: dnsmessage::Class::INET.into(),
+        ttl: 300,
+        data: dnsmessage::ResourceData::TXT {
+            txt: vec![b"v=spf1 ".to_vec(), b"-all".to_vec()],
+        },
+    };
+
+    let record = simple_dns::ResourceRecord::try_from(resource.clone()).unwrap();
+    let simple_dns::rdata::RData::TXT(txt) = &record.rdata else {
+        panic!("unexpected rdata: {:?}", record.rdata);
+    };
+    assert_eq!(String::try_from(txt.clone()).unwrap(), "v=spf1 -all");
+
+    assert_eq!(dnsmessage::Resource::try_from(record).unwrap(), resource);
+}
+
+#[test]
+fn test_interop_other_types() {
+    let record = simple_dns::ResourceRecord::new(
+        simple_dns::Name::new("example.org").unwrap(),
+        simple_dns::CLASS::IN,
+        300,
+        simple_dns::rdata::RData::AAAA(simple_dns::rdata::AAAA::from(Ipv6Addr::LOCALHOST)),
+    );
+    let resource = dnsmessage::OwnedResource::try_from(record).unwrap();
+    assert_eq!(
+        resource.data,
+        dnsmessage::ResourceData::AAAA {
+            aaaa: Ipv6Addr::LOCALHOST
+        }
+    );
+
+    let resource = dnsmessage::Resource {
+        name: "example.org.".to_string(),
+        class: dnsmessage::Class::INET.into(),
+        ttl: 300,
+        data: dnsmessage::ResourceData::Unknown {
+            typ: dnsmessage::MaybeUnknown::Unknown(65280),
+            data: vec![1, 2, 3],
+        },
+    };
+    let record = simple_dns::ResourceRecord::try_from(resource.clone()).unwrap();
+    let simple_dns::rdata::RData::NULL(65280, data) = &record.rdata else {
+        panic!("unexpected rdata: {:?}", record.rdata);
+    };
+    assert_eq!(data.get_data(), &[1, 2, 3]);
+
+    assert_eq!(dnsmessage::Resource::try_from(record).unwrap(), resource);
+}
@@ -59,3 +59,92 @@ fn test_modify() {
     assert_eq!(answer.data, dnsmessage::ResourceData::A { a: Ipv4Addr::BROADCAST });
     assert!(answers.next().is_none());
 }
+
+#[test]
+fn test_cursor_offset() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(3456, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.bilibili.com.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 255,
+            data: dnsmessage::ResourceData::A { a: Ipv4Addr::LOCALHOST },
+        })
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "api.bilibili.com.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 255,
+            data: dnsmessage::ResourceData::A { a: Ipv4Addr::BROADCAST },
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let mut pkt = dnsmessage::Packet::new(pkt).unwrap();
+    let mut answers_cursor = pkt.answers_cursor();
+    assert!(matches!(answers_cursor.offset(), Err(dnsmessage::Error::InvalidCursorState)));
+
+    let mut offsets = Vec::new();
+    while let Ok(true) = answers_cursor.next() {
+        offsets.push(answers_cursor.offset().unwrap());
+    }
+
+    // "www.bilibili.com." (18) + type, class, ttl, rdlength (10) + address (4)
+    assert_eq!(offsets, vec![12, 44]);
+    assert_eq!(pkt[12], 3);
+    assert_eq!(&pkt[44..48], b"\x03api");
+}
+
+#[test]
+fn test_verify_counts() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(5150, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.bilibili.com.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.bilibili.com.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 255,
+            data: dnsmessage::ResourceData::A { a: Ipv4Addr::LOCALHOST },
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let mut pkt = dnsmessage::Packet::new(pkt).unwrap();
+    pkt.verify_counts().unwrap();
+
+    pkt.as_mut_bytes()[7] = 2;
+    assert!(matches!(
+        pkt.verify_counts(),
+        Err(dnsmessage::Error::CountMismatch {
+            section: "answer",
+            declared: 2,
+            actual: 1,
+        })
+    ));
+    assert_eq!(pkt.answers_len(), 1);
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "nsec3")]
+
+#[test]
+fn test_hashed_name() {
+    let salt = [0xaau8, 0xbb, 0xcc, 0xdd];
+
+    let name = b"\x07example\x00";
+    let name = dnsmessage::NameVisitor::new(&name[..], 0);
+    assert_eq!(
+        dnsmessage::hashed_name(&name, &salt, 12).unwrap(),
+        [
+            0x06, 0x53, 0x68, 0xab, 0xee, 0xd7, 0xec, 0x6e, 0x9f, 0xeb, 0xa9, 0x6b, 0x8c, 0x8b, 0xc3, 0xe8, 0xb7, 0x91, 0xf7,
+            0x16,
+        ]
+    );
+
+    let name = b"\x01A\x07EXAMPLE\x00";
+    let name = dnsmessage::NameVisitor::new(&name[..], 0);
+    assert_eq!(
+        dnsmessage::hashed_name(&name, &salt, 12).unwrap(),
+        [
+            0x19, 0x6d, 0xd8, 0xc3, 0x30, 0x67, 0x83, 0xa8, 0x19, 0x0f, 0x52, 0xc2, 0x62, 0xd2, 0xb7, 0xe5, 0xe8, 0x36, 0xe7,
+            0xf5,
+        ]
+    );
+}
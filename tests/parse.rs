@@ -262,3 +262,899 @@ fn test_parse() {
     );
     assert!(additionals.next().is_none());
 }
+
+#[test]
+fn test_parse_short_rdata() {
+    let mut pkt = vec![
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x01, b'a', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+    ];
+    assert!(dnsmessage::Packet::new(&pkt[..]).is_ok());
+
+    pkt[24] = 0x40;
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+}
+
+#[test]
+fn test_parse_huge_counts() {
+    let pkt = [
+        0x04, 0xd2, 0x01, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+        0x00, 0x00, 0x01, 0x00, 0x01, // Question
+        0x00, 0x00, 0x01, 0x00, 0x01, // Question
+    ];
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+
+    let pkt = [
+        0x04, 0xd2, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, // Header
+    ];
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+}
+
+#[test]
+fn test_parse_empty_label() {
+    let builder = dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 1234,
+            resp: false,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_DESIRED,
+        })
+        .unwrap();
+    assert!(matches!(
+        builder.write_question(&dnsmessage::Question {
+            name: "a..b.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        }),
+        Err(dnsmessage::Error::EmptyLabel)
+    ));
+
+    // On the wire an empty label is the terminator, so `a..b.example.org.` ends the name early
+    let pkt = [
+        0x04, 0xd2, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+        0x01, b'a', 0x00, 0x01, b'b', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+    ];
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]),
+        Err(dnsmessage::Error::PacketSizeMismatch)
+    ));
+
+    // Inside rdata the early terminator leaves octets behind the name
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x00, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0x00, 0x12, // Root, CNAME, Class, TTL, Data length
+        0x01, b'a', 0x00, 0x01, b'b', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Data
+    ];
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(matches!(pkt.answers().next().unwrap(), Err(dnsmessage::Error::EmptyLabel)));
+
+    // Octets behind the name that aren't labels are a size mismatch instead
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x00, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0x00, 0x04, // Root, CNAME, Class, TTL, Data length
+        0x01, b'a', 0x00, 0x07, // Data
+    ];
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(matches!(
+        pkt.answers().next().unwrap(),
+        Err(dnsmessage::Error::PacketSizeMismatch)
+    ));
+
+    // A dot inside a label is escaped to tell it apart from two labels in presentation format
+    let name = b"\x03a.b\x07example\x03org\x00";
+    let name = dnsmessage::NameVisitor::new(&name[..], 0);
+    assert_eq!(TryInto::<String>::try_into(name).unwrap(), "a\\.b.example.org.");
+}
+
+#[test]
+fn test_parse_header_predicates() {
+    let pkt = dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(
+            dnsmessage::Header::response(4321, dnsmessage::RCode::NameError.into())
+                .with_flags(dnsmessage::HeaderFlags::AUTHORITATIVE | dnsmessage::HeaderFlags::TRUNCATED),
+        )
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(pkt).unwrap();
+    assert!(pkt.is_response());
+    assert!(pkt.is_truncated());
+    assert!(pkt.is_authoritative());
+    assert!(!pkt.is_recursion_available());
+    assert_eq!(pkt.rcode(), dnsmessage::RCode::NameError.into());
+
+    let header = pkt.header().unwrap();
+    assert!(header.resp);
+    assert_eq!(header.opcode, 0);
+    assert_eq!(header.rcode, dnsmessage::RCode::NameError.into());
+}
+
+#[test]
+fn test_parse_pointer_into_label() {
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0xc0, 0x12, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+    ];
+
+    let name = dnsmessage::NameVisitor::new(&pkt[..], 33);
+    assert!(matches!(
+        TryInto::<String>::try_into(name),
+        Err(dnsmessage::Error::InvalidNameSegmentBody)
+    ));
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    assert!(matches!(
+        TryInto::<String>::try_into(answer.name),
+        Err(dnsmessage::Error::InvalidNameSegmentBody)
+    ));
+
+    let name = dnsmessage::NameVisitor::new(&pkt[..], 16);
+    assert_eq!(TryInto::<String>::try_into(name).unwrap(), "example.org.");
+}
+
+#[test]
+fn test_parse_name_lengths() {
+    let name = b"\x03www\x07example\x03org\x00";
+    let name = dnsmessage::NameVisitor::new(&name[..], 0);
+    assert_eq!(name.label_count().unwrap(), 3);
+    assert_eq!(name.presentation_len().unwrap(), "www.example.org.".len());
+
+    let name = b"\x00";
+    let name = dnsmessage::NameVisitor::new(&name[..], 0);
+    assert_eq!(name.label_count().unwrap(), 0);
+    assert_eq!(name.presentation_len().unwrap(), ".".len());
+}
+
+#[test]
+fn test_parse_name_compression() {
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0x04, b'm', b'a', b'i', b'l', 0xc0, 0x10, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+    ];
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let question = pkt.questions().next().unwrap().unwrap();
+    assert!(!question.name.is_compressed().unwrap());
+
+    let answer = pkt.answers().next().unwrap().unwrap();
+    assert!(answer.name.is_compressed().unwrap());
+    assert_eq!(TryInto::<String>::try_into(answer.name).unwrap(), "mail.example.org.");
+
+    let name = dnsmessage::NameVisitor::new(&b"\x03www\x07exam"[..], 0);
+    assert!(matches!(name.is_compressed(), Err(dnsmessage::Error::ShortBuffer)));
+}
+
+#[test]
+fn test_parse_question_matches() {
+    let pkt = [
+        0x04, 0xd2, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'W', b'w', b'W', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x1c, 0x00, 0x01, // Type, Class
+    ];
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let question = pkt.questions().next().unwrap().unwrap();
+
+    let aaaa = dnsmessage::Type::AAAA.into();
+    let inet = dnsmessage::Class::INET.into();
+    assert!(question.matches("www.example.org.", aaaa, inet).unwrap());
+    assert!(question.matches("WWW.Example.ORG", aaaa, inet).unwrap());
+    assert!(
+        !question
+            .matches("www.example.org.", dnsmessage::Type::A.into(), inet)
+            .unwrap()
+    );
+    assert!(
+        !question
+            .matches("www.example.org.", aaaa, dnsmessage::Class::CHAOS.into())
+            .unwrap()
+    );
+    assert!(!question.matches("example.org.", aaaa, inet).unwrap());
+    assert!(!question.matches("www.example.org.net.", aaaa, inet).unwrap());
+    assert!(!question.matches(".", aaaa, inet).unwrap());
+}
+
+#[test]
+fn test_parse_question_cache_key() {
+    let pkt = [
+        0x04, 0xd2, 0x01, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'W', b'W', b'W', 0x07, b'E', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'O', b'R', b'G', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+    ];
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let mut questions = pkt.questions();
+    let upper = questions.next().unwrap().unwrap().cache_key().unwrap();
+    let lower = questions.next().unwrap().unwrap().cache_key().unwrap();
+
+    assert_eq!(upper, lower);
+    assert_eq!(
+        upper,
+        (
+            "www.example.org.".to_string(),
+            dnsmessage::Type::A.into(),
+            dnsmessage::Class::INET.into()
+        )
+    );
+}
+
+#[test]
+fn test_parse_single_question() {
+    let pkt = [
+        0x04, 0xd2, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+        0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+    ];
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let question = pkt.single_question().unwrap();
+    assert_eq!(
+        question.try_into_owned::<String>().unwrap(),
+        dnsmessage::Question {
+            name: "example.org.".to_string(),
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        }
+    );
+
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+    ];
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(matches!(
+        pkt.single_question(),
+        Err(dnsmessage::Error::UnexpectedQuestionCount(0))
+    ));
+}
+
+#[test]
+fn test_parse_offset_overflow() {
+    let pkt = [0xc0u8, 0x00, 0x00];
+
+    for offset in [usize::MAX, usize::MAX - 1, usize::MAX / 2] {
+        let name = dnsmessage::NameVisitor::new(&pkt[..], offset);
+        assert!(matches!(
+            TryInto::<String>::try_into(&name),
+            Err(dnsmessage::Error::ShortBuffer)
+        ));
+        assert!(matches!(name.label_count(), Err(dnsmessage::Error::ShortBuffer)));
+    }
+
+    let bitmap = vec![0xffu8; 8200];
+    let data = dnsmessage::ResourceData::<&str, &[u8]>::WKS {
+        address: Ipv4Addr::LOCALHOST,
+        protocol: 6,
+        bitmap: &bitmap,
+    };
+    assert_eq!(data.ports().count(), 65536);
+    assert_eq!(data.ports().last(), Some(u16::MAX));
+
+    // every rdata arm either parses or fails on short and garbled data, and never slices past the record
+    for typ in (0..=u16::MAX).filter(|typ| dnsmessage::Type::try_from(*typ).is_ok()) {
+        for len in 0..40u8 {
+            for fill in [0x00, 0x01, 0x02, 0xff] {
+                let mut pkt = vec![
+                    0x04, 0xd2, 0x81, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+                    0x00, // Root
+                ];
+                pkt.extend_from_slice(&typ.to_be_bytes());
+                pkt.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0x00, len]);
+                pkt.extend((0..len).map(|idx| if idx % 3 == 0 { fill } else { idx }));
+
+                if let Ok(parsed) = dnsmessage::Packet::new(&pkt[..]) {
+                    let _ = parsed.answers().next();
+                }
+
+                if len > 0 {
+                    let truncated = &pkt[..pkt.len() - 1];
+                    assert!(dnsmessage::Packet::new(truncated).is_err());
+
+                    let lazy = dnsmessage::LazyPacket::new(truncated).unwrap();
+                    assert!(matches!(
+                        lazy.answers().unwrap().next(),
+                        Some(Err(dnsmessage::Error::ShortBuffer))
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_txt_boundaries() {
+    let mut pkt = vec![
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, // Header
+        0x01, b'a', 0x00, // Name
+        0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x07, 0x05, b'h', b'e', b'l', b'l', b'o', 0x00, // Data
+        0xc0, 0x0c, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+    ];
+
+    let packet = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = packet.answers().next().unwrap().unwrap();
+    assert_eq!(
+        answer.data,
+        dnsmessage::ResourceData::TXT {
+            txt: vec![&b"hello"[..], &b""[..]]
+        }
+    );
+
+    // the final string claims more octets than its rdata has left, though the packet has plenty
+    pkt[31] = 0x03;
+    let packet = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(matches!(
+        packet.answers().next(),
+        Some(Err(dnsmessage::Error::PacketSizeMismatch))
+    ));
+}
+
+#[test]
+fn test_parse_all_seeds() {
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0xc0, 0x0c, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+    ];
+    assert!(dnsmessage::parse_all(&pkt).is_ok());
+
+    // truncated header
+    assert!(matches!(
+        dnsmessage::parse_all(&pkt[..5]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+
+    // rdata length far beyond the end of the packet
+    let mut giant = pkt;
+    giant[43..45].copy_from_slice(&[0xff, 0xff]);
+    assert!(matches!(dnsmessage::parse_all(&giant), Err(dnsmessage::Error::ShortBuffer)));
+
+    // question name pointing into a chain of twelve pointers stored in the answer rdata
+    let mut chain = vec![
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0xc0, 0x1d, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0x00, // Name
+        0xff, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x19, // Data length
+    ];
+    for idx in 1..=12u8 {
+        chain.extend_from_slice(&[0xc0, 0x1d + idx * 2]);
+    }
+    chain.push(0x00);
+    assert!(matches!(
+        dnsmessage::parse_all(&chain),
+        Err(dnsmessage::Error::TooManyPointers)
+    ));
+
+    // rdata cut short, which only the lazy parser would walk into
+    let cut = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x00, // Name
+        0x00, 0x3b, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // CDS, Class, TTL
+        0x00, 0x2d, 0x0c, 0x0c, 0x0c, 0xff, // Data length, Key tag, Algorithm, Digest type
+    ];
+    assert!(matches!(dnsmessage::parse_all(&cut), Err(dnsmessage::Error::ShortBuffer)));
+
+    // a pointer to itself
+    let looping = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Header
+        0xc0, 0x0c, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+    ];
+    assert!(matches!(
+        dnsmessage::parse_all(&looping),
+        Err(dnsmessage::Error::TooManyPointers)
+    ));
+}
+
+#[test]
+fn test_parse_cname_chain() {
+    let mut builder = dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(6204, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap();
+    let answers: [dnsmessage::Resource<&str, &[u8]>; 4] = [
+        dnsmessage::Resource {
+            name: "b.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(1, 2, 3, 4),
+            },
+        },
+        dnsmessage::Resource {
+            name: "A.Example.ORG.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::CNAME { cname: "b.example.org." },
+        },
+        dnsmessage::Resource {
+            name: "loop1.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::CNAME {
+                cname: "loop2.example.org.",
+            },
+        },
+        dnsmessage::Resource {
+            name: "loop2.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::CNAME {
+                cname: "loop1.example.org.",
+            },
+        },
+    ];
+    for answer in &answers {
+        builder = builder.write_answer(answer).unwrap();
+    }
+    let pkt = builder
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    assert_eq!(
+        pkt.resolve_cname_chain("a.example.org.").unwrap(),
+        vec!["a.example.org.", "b.example.org."]
+    );
+    assert_eq!(pkt.resolve_cname_chain("b.example.org.").unwrap(), vec!["b.example.org."]);
+    assert_eq!(
+        pkt.resolve_cname_chain("loop1.example.org.").unwrap(),
+        vec!["loop1.example.org.", "loop2.example.org."]
+    );
+}
+
+#[test]
+fn test_parse_first_address() {
+    fn build(typ: dnsmessage::Type, answers: &[dnsmessage::Resource<&str, &[u8]>]) -> Vec<u8> {
+        let mut builder = dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::response(7031, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .write_question(&dnsmessage::Question {
+                name: "www.example.org.",
+                typ: typ.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .unwrap()
+            .finish_questions()
+            .unwrap();
+        for answer in answers {
+            builder = builder.write_answer(answer).unwrap();
+        }
+        builder
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+            .into_inner()
+    }
+
+    let pkt = build(
+        dnsmessage::Type::A,
+        &[
+            dnsmessage::Resource {
+                name: "other.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::A {
+                    a: Ipv4Addr::new(5, 6, 7, 8),
+                },
+            },
+            dnsmessage::Resource {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::CNAME {
+                    cname: "cdn.example.net.",
+                },
+            },
+            dnsmessage::Resource {
+                name: "cdn.example.net.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::A {
+                    a: Ipv4Addr::new(1, 2, 3, 4),
+                },
+            },
+        ],
+    );
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.first_a().unwrap(), Some(Ipv4Addr::new(1, 2, 3, 4)));
+    assert_eq!(pkt.first_aaaa().unwrap(), None);
+
+    let pkt = build(
+        dnsmessage::Type::AAAA,
+        &[
+            dnsmessage::Resource {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::AAAA {
+                    aaaa: Ipv6Addr::LOCALHOST,
+                },
+            },
+            dnsmessage::Resource {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 300,
+                data: dnsmessage::ResourceData::AAAA {
+                    aaaa: Ipv6Addr::UNSPECIFIED,
+                },
+            },
+        ],
+    );
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.first_aaaa().unwrap(), Some(Ipv6Addr::LOCALHOST));
+    assert_eq!(pkt.first_a().unwrap(), None);
+}
+
+#[test]
+fn test_parse_labels_owned() {
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0x04, b'm', b'a', b'i', b'l', 0xc0, 0x10, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+    ];
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let question = pkt.questions().next().unwrap().unwrap();
+    assert_eq!(question.name.labels_owned().unwrap(), vec!["www", "example", "org"]);
+
+    let answer = pkt.answers().next().unwrap().unwrap();
+    assert_eq!(answer.name.labels_owned().unwrap(), vec!["mail", "example", "org"]);
+
+    let name = dnsmessage::NameVisitor::new(&b"\x02\xff\xfe\x00"[..], 0);
+    assert!(matches!(name.labels_owned(), Err(dnsmessage::Error::InvalidNameSegmentBody)));
+}
+
+#[test]
+fn test_parse_answers_lenient() {
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 1, // Data
+        0xc0, 0x0c, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, 0, 2, // Data
+        0xc0, 0x0c, // Name
+        0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x04, 127, 0, // Data, cut off by the end of the packet
+    ];
+    let expected = vec![
+        dnsmessage::ResourceData::A {
+            a: Ipv4Addr::new(127, 0, 0, 1),
+        },
+        dnsmessage::ResourceData::A {
+            a: Ipv4Addr::new(127, 0, 0, 2),
+        },
+    ];
+
+    // the last answer is cut short, then missing altogether
+    for len in [pkt.len(), 59] {
+        assert!(dnsmessage::Packet::new(&pkt[..len]).is_err());
+
+        let pkt = dnsmessage::LazyPacket::new(&pkt[..len]).unwrap();
+        assert!(pkt.answers().unwrap().nth(2).unwrap().is_err());
+
+        let answers = pkt.answers_lenient().map(|answer| answer.data).collect::<Vec<_>>();
+        assert_eq!(answers, expected);
+    }
+}
+
+#[test]
+fn test_parse_semantically_eq() {
+    fn build(id: u16, ttl: u32, cname: &str, a: Ipv4Addr) -> Vec<u8> {
+        dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::response(id, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .write_question(&dnsmessage::Question {
+                name: "www.example.org.",
+                typ: dnsmessage::Type::A.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl,
+                data: dnsmessage::ResourceData::CNAME { cname },
+            })
+            .unwrap()
+            .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+                name: cname,
+                class: dnsmessage::Class::INET.into(),
+                ttl,
+                data: dnsmessage::ResourceData::A { a },
+            })
+            .unwrap()
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+            .into_inner()
+    }
+
+    let a = build(1, 300, "cdn.example.net.", Ipv4Addr::new(192, 0, 2, 1));
+    let a = dnsmessage::Packet::new(&a[..]).unwrap();
+
+    let b = build(2, 17, "CDN.Example.NET.", Ipv4Addr::new(192, 0, 2, 1));
+    let b = dnsmessage::Packet::new(&b[..]).unwrap();
+    assert!(a.semantically_eq(&b).unwrap());
+    assert!(b.semantically_eq(&a).unwrap());
+
+    let c = build(1, 300, "cdn.example.net.", Ipv4Addr::new(192, 0, 2, 2));
+    let c = dnsmessage::Packet::new(&c[..]).unwrap();
+    assert!(!a.semantically_eq(&c).unwrap());
+}
+
+#[test]
+fn test_parse_glue_for() {
+    let ns = |ns: &'static str| dnsmessage::Resource::<_, &[u8]> {
+        name: "example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl: 172800,
+        data: dnsmessage::ResourceData::NS { ns },
+    };
+    let glue = |name: &'static str, data: dnsmessage::ResourceData<&'static str, &'static [u8]>| dnsmessage::Resource {
+        name,
+        class: dnsmessage::Class::INET.into(),
+        ttl: 172800,
+        data,
+    };
+
+    let pkt = dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(5521, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .write_authority(&ns("a.iana-servers.net."))
+        .unwrap()
+        .write_authority(&ns("b.iana-servers.net."))
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .write_additional(&glue(
+            "a.iana-servers.net.",
+            dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(199, 43, 135, 53),
+            },
+        ))
+        .unwrap()
+        .write_additional(&glue(
+            "b.iana-servers.net.",
+            dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(199, 43, 133, 53),
+            },
+        ))
+        .unwrap()
+        .write_additional(&glue(
+            "A.IANA-SERVERS.NET.",
+            dnsmessage::ResourceData::AAAA {
+                aaaa: "2001:500:8f::53".parse().unwrap(),
+            },
+        ))
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    assert_eq!(
+        pkt.glue_for("a.iana-servers.net.").unwrap(),
+        vec![
+            dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(199, 43, 135, 53)
+            },
+            dnsmessage::ResourceData::AAAA {
+                aaaa: "2001:500:8f::53".parse().unwrap()
+            },
+        ]
+    );
+    assert!(pkt.glue_for("c.iana-servers.net.").unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_name_eq_hash() {
+    use std::hash::{BuildHasher, RandomState};
+
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x01, 0x00, 0x01, // Type, Class
+        0x07, b'E', b'x', b'A', b'm', b'P', b'l', b'E', 0x03, b'o', b'r', b'g', 0x00, // Name
+        0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+        0x00, 0x02, 0xc0, 0x10, // Data
+    ];
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    let dnsmessage::ResourceData::NS { ns } = answer.data else {
+        panic!("unexpected data: {:?}", answer.data);
+    };
+
+    // "ExAmPlE.org." spelled out versus "example.org." reached through a pointer into the question
+    assert!(ns.is_compressed().unwrap());
+    assert!(!answer.name.is_compressed().unwrap());
+    assert_eq!(answer.name, ns);
+
+    let state = RandomState::new();
+    assert_eq!(state.hash_one(&answer.name), state.hash_one(&ns));
+
+    let question = pkt.questions().next().unwrap().unwrap();
+    assert_ne!(question.name, ns);
+}
+
+#[test]
+fn test_parse_limits() {
+    let limits = dnsmessage::ParseLimits {
+        questions: 1,
+        answers: 64,
+        authorities: 64,
+        additionals: 64,
+        records: 128,
+    };
+
+    let mut pkt = vec![
+        0x04, 0xd2, 0x81, 0x80, 0x00, 0x00, 0x27, 0x10, 0x00, 0x00, 0x00, 0x00, // Header, 10000 answers
+    ];
+    for _ in 0..10000 {
+        pkt.extend_from_slice(&[
+            0x00, // Name
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, // Type, Class, TTL
+            0x00, 0x04, 127, 0, 0, 1, // Data
+        ]);
+    }
+    assert!(dnsmessage::Packet::new(&pkt[..]).is_ok());
+    assert!(matches!(
+        dnsmessage::Packet::new_with_limits(&pkt[..], limits),
+        Err(dnsmessage::Error::TooManyRecords)
+    ));
+
+    // each section within its own limit, but more records than allowed in total
+    pkt[6..12].copy_from_slice(&[0x00, 0x40, 0x00, 0x40, 0x00, 0x40]);
+    pkt.truncate(12 + 192 * 15);
+    assert!(dnsmessage::Packet::new(&pkt[..]).is_ok());
+    assert!(matches!(
+        dnsmessage::Packet::new_with_limits(&pkt[..], limits),
+        Err(dnsmessage::Error::TooManyRecords)
+    ));
+
+    pkt[6..12].copy_from_slice(&[0x00, 0x40, 0x00, 0x40, 0x00, 0x00]);
+    pkt.truncate(12 + 128 * 15);
+    assert_eq!(
+        dnsmessage::Packet::new_with_limits(&pkt[..], limits).unwrap().answers_len(),
+        64
+    );
+}
+
+#[test]
+fn test_parse_reverse_ip() {
+    let reverse = |name: &str| {
+        let pkt = dnsmessage::Builder::new(std::io::Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::query(7177))
+            .unwrap()
+            .write_question(&dnsmessage::Question {
+                name,
+                typ: dnsmessage::Type::PTR.into(),
+                class: dnsmessage::Class::INET.into(),
+            })
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .finish_answers()
+            .unwrap()
+            .finish_authorities()
+            .unwrap()
+            .finish_additionals()
+            .unwrap()
+            .into_inner();
+        let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+        pkt.single_question().unwrap().name.to_reverse_ip().unwrap()
+    };
+
+    assert_eq!(reverse("4.3.2.1.in-addr.arpa."), Some(Ipv4Addr::new(1, 2, 3, 4).into()));
+    assert_eq!(reverse("4.3.2.1.IN-ADDR.ARPA."), Some(Ipv4Addr::new(1, 2, 3, 4).into()));
+    assert_eq!(
+        reverse("b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."),
+        Some("2001:db8::567:89ab".parse::<Ipv6Addr>().unwrap().into())
+    );
+
+    let ip = "2001:db8::567:89ab".parse::<Ipv6Addr>().unwrap().into();
+    assert_eq!(reverse(&dnsmessage::Question::reverse(ip).name), Some(ip));
+
+    assert_eq!(reverse("www.example.org."), None);
+    assert_eq!(reverse("3.2.1.in-addr.arpa."), None);
+    assert_eq!(reverse("256.3.2.1.in-addr.arpa."), None);
+    assert_eq!(reverse("+4.3.2.1.in-addr.arpa."), None);
+    assert_eq!(reverse("04.3.2.1.in-addr.arpa."), None);
+    assert_eq!(
+        reverse("g.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."),
+        None
+    );
+    assert_eq!(reverse("arpa."), None);
+    assert_eq!(reverse("."), None);
+}
+
+#[test]
+fn test_lazy_packet_truncated_rdata() {
+    let pkt = [
+        0x04, 0xd2, 0x81, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Header
+        0x00, 0x00, 0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0x00, 0x08, // Root, WKS, Class, TTL, Data length
+        127, 0, 0, 1, 0x06, // Data, the bitmap is missing
+    ];
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]),
+        Err(dnsmessage::Error::ShortBuffer)
+    ));
+
+    let lazy = dnsmessage::LazyPacket::new(&pkt[..]).unwrap();
+    let mut answers = lazy.answers().unwrap();
+    assert!(matches!(answers.next(), Some(Err(dnsmessage::Error::ShortBuffer))));
+}
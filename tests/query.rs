@@ -48,3 +48,37 @@ fn test_query() {
         }
     }
 }
+
+#[test]
+#[ignore]
+fn test_recv_packet() {
+    let sk = UdpSocket::bind("0.0.0.0:0").unwrap();
+    sk.connect("1.1.1.1:53").unwrap();
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::with_capacity(512)))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(1146).with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    sk.send(&pkt).unwrap();
+
+    let mut buf = [0u8; 512];
+    let pkt = dnsmessage::recv_packet(&sk, &mut buf).unwrap();
+    assert_eq!(pkt.header().unwrap().id, 1146);
+    assert!(pkt.is_response());
+}
@@ -0,0 +1,699 @@
+use std::{io::Cursor, net::Ipv4Addr};
+
+fn build_answer(data: &dnsmessage::ResourceData<&str, &[u8]>) -> Vec<u8> {
+    dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 810,
+            resp: true,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::empty(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource {
+            name: "www.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 255,
+            data: data.clone(),
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner()
+}
+
+fn assert_roundtrip(data: dnsmessage::ResourceData<&str, &[u8]>) {
+    let pkt = build_answer(&data);
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+
+    let mut answers = pkt.answers();
+    let answer = answers.next().unwrap().unwrap();
+    assert!(answers.next().is_none());
+
+    assert_eq!(TryInto::<String>::try_into(answer.name).unwrap(), "www.example.org.");
+    assert_eq!(
+        answer.data.try_into_owned::<String, Vec<u8>>().unwrap(),
+        data.try_into_owned::<String, Vec<u8>>().unwrap()
+    );
+}
+
+#[test]
+fn test_cert() {
+    let certificate = b"\x99\x00\x0d\x04\x5f\x3a\x2c\x11\x01\x08\x00\xa1\xb2";
+    assert_roundtrip(dnsmessage::ResourceData::CERT {
+        cert_type: dnsmessage::CertType::PGP.into(),
+        key_tag: 0,
+        algorithm: 0,
+        certificate: &certificate[..],
+    });
+    assert_roundtrip(dnsmessage::ResourceData::CERT {
+        cert_type: dnsmessage::MaybeUnknown::Unknown(65280),
+        key_tag: 12345,
+        algorithm: 8,
+        certificate: &certificate[..],
+    });
+}
+
+#[test]
+fn test_wks() {
+    let bitmap = [0u8, 0, 0, 0x40, 0, 0, 0, 0, 0, 0, 0x80];
+    let data = dnsmessage::ResourceData::WKS {
+        address: Ipv4Addr::from([192u8, 0, 2, 1]),
+        protocol: 6,
+        bitmap: &bitmap[..],
+    };
+
+    assert_eq!(data.ports().collect::<Vec<_>>(), vec![25, 80]);
+    assert_roundtrip(data);
+}
+
+#[test]
+fn test_minfo() {
+    assert_roundtrip(dnsmessage::ResourceData::MINFO {
+        rmailbx: "admin.example.org.",
+        emailbx: "errors.example.org.",
+    });
+}
+
+#[test]
+fn test_rp() {
+    let data = dnsmessage::ResourceData::RP {
+        mbox: "admin.example.org.",
+        txt: "contact.example.org.",
+    };
+    assert_roundtrip(data.clone());
+
+    // neither name is compressed against the owner name (RFC 3597 section 4)
+    let pkt = build_answer(&data);
+    assert!(pkt.ends_with(b"\x05admin\x07example\x03org\x00\x07contact\x07example\x03org\x00"));
+}
+
+#[test]
+fn test_spf() {
+    assert_roundtrip(dnsmessage::ResourceData::SPF {
+        txt: vec![b"v=spf1 ", b"include:_spf.example.org ~all"],
+    });
+}
+
+#[test]
+fn test_nsec3() {
+    let salt = [0xaau8, 0xbb, 0xcc, 0xdd];
+    let next_hashed = [
+        0x06u8, 0x53, 0x68, 0xab, 0xee, 0xd7, 0xec, 0x6e, 0x9f, 0xeb, 0xa9, 0x6b, 0x8c, 0x8b, 0xc3, 0xe8, 0xb7, 0x91, 0xf7, 0x16,
+    ];
+    let type_bitmap = [0u8, 6, 0x40, 0x01, 0, 0, 0, 0x02];
+    let data = dnsmessage::ResourceData::NSEC3 {
+        hash_algorithm: 1,
+        flags: 1,
+        iterations: 12,
+        salt: &salt[..],
+        next_hashed: &next_hashed[..],
+        type_bitmap: &type_bitmap[..],
+    };
+
+    assert_eq!(
+        data.types().collect::<Vec<_>>(),
+        vec![
+            dnsmessage::Type::A.into(),
+            dnsmessage::Type::MX.into(),
+            dnsmessage::MaybeUnknown::Unknown(46),
+        ]
+    );
+    assert_roundtrip(data);
+}
+
+#[test]
+fn test_nsec3_type_windows() {
+    let mut type_bitmap = vec![0u8, 32];
+    type_bitmap.extend_from_slice(&[0u8; 32]);
+    type_bitmap[2] = 0x40; // A
+    type_bitmap[2 + 31] = 0x01; // ANY
+    type_bitmap.extend_from_slice(&[1, 1, 0x40]); // CAA
+    type_bitmap.extend_from_slice(&[0x80, 1, 0x40]); // 32769
+
+    let data = dnsmessage::ResourceData::NSEC3 {
+        hash_algorithm: 1,
+        flags: 0,
+        iterations: 0,
+        salt: &[][..],
+        next_hashed: &[0u8; 20][..],
+        type_bitmap: &type_bitmap[..],
+    };
+    assert_eq!(
+        data.types().collect::<Vec<_>>(),
+        vec![
+            dnsmessage::Type::A.into(),
+            dnsmessage::Type::ALL.into(),
+            dnsmessage::MaybeUnknown::Unknown(257),
+            dnsmessage::MaybeUnknown::Unknown(32769),
+        ]
+    );
+    assert_roundtrip(data);
+
+    // an oversized window would spill into the next window's types
+    let type_bitmap = [&[0u8, 33][..], &[0xff; 33][..]].concat();
+    let data = dnsmessage::ResourceData::<&str, &[u8]>::NSEC3 {
+        hash_algorithm: 1,
+        flags: 0,
+        iterations: 0,
+        salt: &[],
+        next_hashed: &[0u8; 20],
+        type_bitmap: &type_bitmap,
+    };
+    assert_eq!(data.types().count(), 0);
+}
+
+#[test]
+fn test_nsec3param() {
+    let salt = [0xaa, 0xbb, 0xcc, 0xdd];
+    let data = dnsmessage::ResourceData::NSEC3PARAM {
+        hash_algorithm: 1,
+        flags: 0,
+        iterations: 12,
+        salt: &salt[..],
+    };
+
+    let pkt = build_answer(&data);
+    assert_eq!(&pkt[pkt.len() - 11..], &[0, 9, 1, 0, 0, 12, 4, 0xaa, 0xbb, 0xcc, 0xdd]);
+    assert_roundtrip(data);
+}
+
+#[test]
+fn test_kx() {
+    let data = dnsmessage::ResourceData::KX {
+        preference: 10,
+        exchanger: "www.example.org.",
+    };
+    assert_roundtrip(data.clone());
+
+    // the exchanger is never compressed, even though it repeats the owner name
+    let pkt = build_answer(&data);
+    assert!(pkt.ends_with(b"\x00\x0a\x03www\x07example\x03org\x00"));
+}
+
+#[test]
+fn test_cds() {
+    let digest = [
+        0x2b, 0xb1, 0x83, 0xaf, 0x5f, 0x22, 0x58, 0x81, 0x79, 0xa5, 0x3b, 0x0a, 0x98, 0x63, 0x1f, 0xad, 0x1a, 0x29, 0x21, 0x18,
+    ];
+    let data = dnsmessage::ResourceData::CDS {
+        key_tag: 20326,
+        algorithm: 8,
+        digest_type: 1,
+        digest: &digest[..],
+    };
+    assert!(!data.is_delete_request());
+    assert_roundtrip(data);
+
+    // RFC 8078 delete sentinel
+    let data = dnsmessage::ResourceData::CDS {
+        key_tag: 0,
+        algorithm: 0,
+        digest_type: 0,
+        digest: &[0u8][..],
+    };
+    assert!(data.is_delete_request());
+    assert!(build_answer(&data).ends_with(&[0, 5, 0, 0, 0, 0, 0]));
+    assert_roundtrip(data);
+
+    let data = dnsmessage::ResourceData::CDNSKEY {
+        flags: 0,
+        protocol: 3,
+        algorithm: 0,
+        public_key: &[0u8][..],
+    };
+    assert!(data.is_delete_request());
+    assert_roundtrip(data.clone());
+
+    // a digest or key cut off by the end of the packet
+    let cds = dnsmessage::ResourceData::CDS {
+        key_tag: 20326,
+        algorithm: 8,
+        digest_type: 1,
+        digest: &digest[..],
+    };
+    for data in [cds, data] {
+        let pkt = build_answer(&data);
+        let pkt = &pkt[..pkt.len() - 1];
+        assert!(matches!(dnsmessage::Packet::new(pkt), Err(dnsmessage::Error::ShortBuffer)));
+
+        let pkt = dnsmessage::LazyPacket::new(pkt).unwrap();
+        assert!(matches!(
+            pkt.answers().unwrap().next(),
+            Some(Err(dnsmessage::Error::ShortBuffer))
+        ));
+    }
+}
+
+#[test]
+fn test_afsdb() {
+    let data = dnsmessage::ResourceData::AFSDB {
+        subtype: 1,
+        hostname: "afs.example.org.",
+    };
+    assert_roundtrip(data.clone());
+
+    let pkt = build_answer(&data);
+    assert!(pkt.ends_with(b"\x00\x01\x03afs\x07example\x03org\x00"));
+}
+
+#[test]
+fn test_eui48() {
+    assert_roundtrip(dnsmessage::ResourceData::EUI48([0x00, 0x00, 0x5e, 0x00, 0x53, 0x2a]));
+
+    let pkt = build_answer(&dnsmessage::ResourceData::Unknown {
+        typ: dnsmessage::Type::EUI48.into(),
+        data: &[0x00, 0x00, 0x5e, 0x00, 0x53],
+    });
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]).unwrap().answers().next(),
+        Some(Err(dnsmessage::Error::InvalidRecordData))
+    ));
+}
+
+#[test]
+fn test_eui64() {
+    assert_roundtrip(dnsmessage::ResourceData::EUI64([
+        0x00, 0x00, 0x5e, 0xef, 0x10, 0x00, 0x00, 0x2a,
+    ]));
+
+    let pkt = build_answer(&dnsmessage::ResourceData::Unknown {
+        typ: dnsmessage::Type::EUI64.into(),
+        data: &[0x00, 0x00, 0x5e, 0xef, 0x10, 0x00, 0x00, 0x2a, 0x00],
+    });
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]).unwrap().answers().next(),
+        Some(Err(dnsmessage::Error::InvalidRecordData))
+    ));
+}
+
+#[test]
+fn test_apl() {
+    let data = dnsmessage::ResourceData::APL {
+        items: vec![
+            dnsmessage::AplItem {
+                family: 1,
+                prefix: 24,
+                negation: false,
+                afd: &[192u8, 0, 2][..],
+            },
+            dnsmessage::AplItem {
+                family: 2,
+                prefix: 32,
+                negation: true,
+                afd: &[0x20u8, 0x01, 0x0d, 0xb8][..],
+            },
+        ],
+    };
+    assert_roundtrip(data);
+
+    // trailing zero octets of the address are dropped on the wire
+    let pkt = build_answer(&dnsmessage::ResourceData::APL {
+        items: vec![dnsmessage::AplItem {
+            family: 1,
+            prefix: 8,
+            negation: false,
+            afd: &[10u8, 0, 0, 0][..],
+        }],
+    });
+    assert!(pkt.ends_with(&[0x00, 0x05, 0x00, 0x01, 8, 0x01, 10]));
+}
+
+#[test]
+fn test_hip() {
+    let hit = [
+        0x20u8, 0x01, 0x00, 0x10, 0x7b, 0x1a, 0x74, 0xdf, 0x36, 0x56, 0x39, 0xcc, 0x39, 0xf1, 0xd5, 0x78,
+    ];
+    let public_key = [0x03u8, 0x01, 0x00, 0x01, 0xb0, 0x29, 0xf2, 0x2d, 0x9a, 0x5d, 0x30, 0xa2];
+    assert_roundtrip(dnsmessage::ResourceData::HIP {
+        pk_algorithm: 2,
+        hit: &hit[..],
+        public_key: &public_key[..],
+        rendezvous_servers: vec!["rvs.example.org."],
+    });
+    assert_roundtrip(dnsmessage::ResourceData::HIP {
+        pk_algorithm: 2,
+        hit: &hit[..],
+        public_key: &public_key[..],
+        rendezvous_servers: Vec::new(),
+    });
+}
+
+#[test]
+fn test_https() {
+    let data = dnsmessage::ResourceData::HTTPS {
+        priority: 1,
+        target: ".",
+        params: vec![
+            dnsmessage::SvcParam {
+                key: dnsmessage::SvcParamKey::Port.into(),
+                value: &[0x01u8, 0xbb][..],
+            },
+            dnsmessage::SvcParam {
+                key: dnsmessage::SvcParamKey::Alpn.into(),
+                value: &b"\x02h2\x02h3"[..],
+            },
+        ],
+    };
+
+    let pkt = build_answer(&data);
+    assert!(pkt.ends_with(b"\x00\x01\x00\x00\x01\x00\x06\x02h2\x02h3\x00\x03\x00\x02\x01\xbb"));
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    let dnsmessage::ResourceData::HTTPS { priority, params, .. } = answer.data else {
+        panic!("unexpected data: {:?}", answer.data);
+    };
+    assert_eq!(priority, 1);
+    assert_eq!(
+        params.iter().map(|param| param.key).collect::<Vec<_>>(),
+        vec![dnsmessage::SvcParamKey::Alpn.into(), dnsmessage::SvcParamKey::Port.into()]
+    );
+
+    let build = |params: Vec<dnsmessage::SvcParam<&[u8]>>| {
+        dnsmessage::Builder::new(Cursor::new(Vec::new()))
+            .unwrap()
+            .write_header(dnsmessage::Header::response(810, dnsmessage::RCode::Success.into()))
+            .unwrap()
+            .finish_questions()
+            .unwrap()
+            .write_answer(&dnsmessage::Resource {
+                name: "www.example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 255,
+                data: dnsmessage::ResourceData::SVCB {
+                    priority: 1,
+                    target: "svc.example.org.",
+                    params,
+                },
+            })
+            .map(|_| ())
+    };
+
+    let port = dnsmessage::SvcParam {
+        key: dnsmessage::SvcParamKey::Port.into(),
+        value: &[0x01u8, 0xbb][..],
+    };
+    assert!(matches!(
+        build(vec![port.clone(), port.clone()]),
+        Err(dnsmessage::Error::InvalidRecordData)
+    ));
+
+    let mandatory = |keys: &'static [u8]| dnsmessage::SvcParam {
+        key: dnsmessage::SvcParamKey::Mandatory.into(),
+        value: keys,
+    };
+    assert!(build(vec![port.clone(), mandatory(&[0x00, 0x03])]).is_ok());
+    assert!(matches!(
+        build(vec![port.clone(), mandatory(&[0x00, 0x01])]),
+        Err(dnsmessage::Error::InvalidRecordData)
+    ));
+    assert!(matches!(
+        build(vec![port, mandatory(&[0x00, 0x00])]),
+        Err(dnsmessage::Error::InvalidRecordData)
+    ));
+}
+
+#[test]
+fn test_loc() {
+    // cambridge-net.kei.com. LOC 42 21 54 N 71 06 18 W -24m 30m
+    let data = dnsmessage::ResourceData::LOC {
+        version: 0,
+        size: 0x33,
+        horiz_pre: 0x16,
+        vert_pre: 0x13,
+        latitude: (1 << 31) + (42 * 3600 + 21 * 60 + 54) * 1000,
+        longitude: (1 << 31) - (71 * 3600 + 6 * 60 + 18) * 1000,
+        altitude: 10_000_000 - 2400,
+    };
+
+    assert_eq!(data.loc_precisions_cm(), Some((3000, 1_000_000, 1000)));
+    assert_roundtrip(data);
+
+    let pkt = build_answer(&dnsmessage::ResourceData::Unknown {
+        typ: dnsmessage::Type::LOC.into(),
+        data: &[0u8; 15],
+    });
+    assert!(matches!(
+        dnsmessage::Packet::new(&pkt[..]).unwrap().answers().next(),
+        Some(Err(dnsmessage::Error::InvalidRecordData))
+    ));
+}
+
+#[test]
+fn test_type_names() {
+    assert_eq!(dnsmessage::Type::AAAA.name(), Some("AAAA"));
+    assert_eq!(dnsmessage::Type::ALL.name(), Some("ANY"));
+    assert_eq!(dnsmessage::MaybeUnknown::<dnsmessage::Type>::Unknown(99).name(), Some("SPF"));
+    assert_eq!(dnsmessage::MaybeUnknown::<dnsmessage::Type>::Unknown(27).name(), Some("GPOS"));
+    assert_eq!(dnsmessage::MaybeUnknown::<dnsmessage::Type>::Unknown(65280).name(), None);
+
+    assert_eq!(dnsmessage::Type::from_name("https"), Some(dnsmessage::Type::HTTPS.into()));
+    assert_eq!(
+        dnsmessage::Type::from_name("ATMA"),
+        Some(dnsmessage::MaybeUnknown::Unknown(34))
+    );
+    assert_eq!(
+        dnsmessage::Type::from_name("NSAP-PTR"),
+        Some(dnsmessage::MaybeUnknown::Unknown(23))
+    );
+    assert_eq!(dnsmessage::Type::from_name("BOGUS"), None);
+
+    for code in [1, 23, 34, 99, 257, 32769] {
+        let typ = dnsmessage::MaybeUnknown::<dnsmessage::Type>::Unknown(code);
+        let name = typ.name().unwrap();
+        assert_eq!(
+            dnsmessage::MaybeUnknown::<dnsmessage::Type>::from_name(name).map(|typ| typ.name()),
+            Some(Some(name))
+        );
+    }
+}
+
+#[test]
+fn test_owned_resource() {
+    let resource: dnsmessage::OwnedResource = dnsmessage::Resource {
+        name: "www.example.org.".to_string(),
+        class: dnsmessage::Class::INET.into(),
+        ttl: 300,
+        data: dnsmessage::OwnedResourceData::MX {
+            preference: 10,
+            mx: "mail.example.org.".to_string(),
+        },
+    };
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(4860, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&resource)
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer: dnsmessage::OwnedResource = pkt.answers().next().unwrap().unwrap().try_into_owned().unwrap();
+    assert_eq!(answer, resource);
+
+    let question: dnsmessage::OwnedQuestion = dnsmessage::Question::any("www.example.org.").try_into_owned().unwrap();
+    assert_eq!(question.name, resource.name);
+}
+
+#[test]
+fn test_tsig() {
+    let mac = [0x5au8; 32];
+    assert_roundtrip(dnsmessage::ResourceData::TSIG {
+        algorithm: "hmac-sha256.",
+        time_signed: 0x0000_6553_f100,
+        fudge: 300,
+        mac: &mac[..],
+        original_id: 810,
+        error: 0,
+        other: &[],
+    });
+
+    // BADTIME carries the server's time in the other data
+    assert_roundtrip(dnsmessage::ResourceData::TSIG {
+        algorithm: "hmac-sha256.",
+        time_signed: (1 << 48) - 1,
+        fudge: 300,
+        mac: &[],
+        original_id: 810,
+        error: 18,
+        other: &[0x00, 0x00, 0x65, 0x53, 0xf1, 0x2c],
+    });
+
+    let err = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(810))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .write_additional(&dnsmessage::Resource {
+            name: "key.example.org.",
+            class: dnsmessage::Class::ANY.into(),
+            ttl: 0,
+            data: dnsmessage::ResourceData::TSIG {
+                algorithm: "hmac-sha256.",
+                time_signed: 1 << 48,
+                fudge: 300,
+                mac: &mac[..],
+                original_id: 810,
+                error: 0,
+                other: &[][..],
+            },
+        })
+        .err();
+    assert!(matches!(err, Some(dnsmessage::Error::InvalidRecordData)));
+}
+
+#[test]
+fn test_srv_unavailable() {
+    let data = dnsmessage::ResourceData::SRV {
+        priority: 0,
+        weight: 0,
+        port: 0,
+        target: ".",
+    };
+    assert!(data.is_unavailable());
+    assert_roundtrip(data.clone());
+
+    let pkt = build_answer(&data);
+    assert!(pkt.ends_with(&[0, 0, 0, 0, 0, 0, 0]));
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    assert!(answer.data.is_unavailable());
+    let dnsmessage::ResourceData::SRV { target, .. } = answer.data else {
+        panic!("unexpected data: {:?}", answer.data);
+    };
+    assert_eq!(TryInto::<String>::try_into(target).unwrap(), ".");
+
+    assert!(
+        !dnsmessage::ResourceData::<_, &[u8]>::SRV {
+            priority: 10,
+            weight: 5,
+            port: 5060,
+            target: "sip.example.org.",
+        }
+        .is_unavailable()
+    );
+}
+
+#[test]
+fn test_map_names() {
+    let pkt = build_answer(&dnsmessage::ResourceData::TXT {
+        txt: vec![b"v=spf1 ", b"-all"],
+    });
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+
+    let data: dnsmessage::ResourceData<String, &[u8]> = answer.data.map_names().unwrap();
+    assert_eq!(
+        data,
+        dnsmessage::ResourceData::TXT {
+            txt: vec![&b"v=spf1 "[..], &b"-all"[..]]
+        }
+    );
+
+    let pkt = build_answer(&dnsmessage::ResourceData::MX {
+        preference: 10,
+        mx: "mail.example.org.",
+    });
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    assert_eq!(
+        answer.data.map_names::<String>().unwrap(),
+        dnsmessage::ResourceData::<_, &[u8]>::MX {
+            preference: 10,
+            mx: "mail.example.org.".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_txt_split() {
+    let value: Vec<u8> = (0..600).map(|idx| b'a' + (idx % 26) as u8).collect();
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(2718, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_txt_split("www.example.org.", dnsmessage::Class::INET.into(), 300, &value)
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    let dnsmessage::ResourceData::TXT { txt } = &answer.data else {
+        panic!("unexpected data: {:?}", answer.data);
+    };
+    assert_eq!(txt.iter().map(|s| s.len()).collect::<Vec<_>>(), vec![255, 255, 90]);
+    assert_eq!(answer.data.concat(), value);
+}
+
+#[test]
+fn test_soa_rname_email() {
+    assert_eq!(
+        dnsmessage::soa_rname_to_email("hostmaster.example.org."),
+        "hostmaster@example.org"
+    );
+    assert_eq!(
+        dnsmessage::email_to_soa_rname("hostmaster@example.org"),
+        "hostmaster.example.org."
+    );
+
+    assert_eq!(dnsmessage::soa_rname_to_email("a\\.b.example.org."), "a.b@example.org");
+    assert_eq!(dnsmessage::email_to_soa_rname("a.b@example.org"), "a\\.b.example.org.");
+
+    // the escaped dot stays inside the first label on the wire
+    let mbox = dnsmessage::email_to_soa_rname("a.b@example.org");
+    let pkt = build_answer(&dnsmessage::ResourceData::SOA {
+        ns: "ns.example.org.",
+        mbox: &mbox,
+        serial: 1,
+        refresh: 7200,
+        retry: 3600,
+        expire: 1209600,
+        min_ttl: 300,
+    });
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let answer = pkt.answers().next().unwrap().unwrap();
+    let dnsmessage::ResourceData::SOA { mbox, .. } = answer.data else {
+        panic!("unexpected data: {:?}", answer.data);
+    };
+    assert_eq!(
+        mbox.segments().collect::<Result<Vec<_>, _>>().unwrap(),
+        vec![&b"a.b"[..], b"example", b"org"]
+    );
+    assert_eq!(TryInto::<String>::try_into(&mbox).unwrap(), "a\\.b.example.org.");
+    assert_eq!(
+        dnsmessage::soa_rname_to_email(&TryInto::<String>::try_into(&mbox).unwrap()),
+        "a.b@example.org"
+    );
+}
@@ -0,0 +1,49 @@
+use std::io::{Cursor, Read};
+
+#[test]
+fn test_stream() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 5353,
+            resp: false,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::RECURSION_DESIRED,
+        })
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::AAAA.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let mut stream = Vec::new();
+    dnsmessage::write_message(&mut stream, &pkt).unwrap();
+    assert_eq!(stream.len(), pkt.len() + 2);
+    stream.extend_from_slice(b"trailing");
+
+    let mut stream = Cursor::new(stream);
+    let message = dnsmessage::read_message(&mut stream).unwrap();
+    assert_eq!(message, pkt);
+    assert_eq!(dnsmessage::Packet::new(message).unwrap().questions_len(), 1);
+
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"trailing");
+
+    assert!(matches!(
+        dnsmessage::read_message(&mut Cursor::new([0x00u8, 0x10, 0x01])),
+        Err(dnsmessage::Error::IoError(_))
+    ));
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "tokio")]
+
+use std::io::Cursor;
+
+#[tokio::test]
+async fn test_tcp_message() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(5353).with_flags(dnsmessage::HeaderFlags::RECURSION_DESIRED))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::AAAA.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let (mut client, mut server) = tokio::io::duplex(64);
+
+    let (written, message) = tokio::join!(
+        async {
+            dnsmessage::write_tcp_message(&mut client, &pkt).await?;
+            dnsmessage::write_tcp_message(&mut client, &pkt).await
+        },
+        async {
+            dnsmessage::read_tcp_message(&mut server).await?;
+            dnsmessage::read_tcp_message(&mut server).await
+        }
+    );
+    written.unwrap();
+
+    let message = message.unwrap();
+    assert_eq!(message, pkt);
+    assert_eq!(dnsmessage::Packet::new(message).unwrap().questions_len(), 1);
+
+    drop(client);
+    assert!(matches!(
+        dnsmessage::read_tcp_message(&mut server).await,
+        Err(dnsmessage::Error::IoError(_))
+    ));
+}
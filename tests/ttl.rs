@@ -0,0 +1,261 @@
+use std::{io::Cursor, net::Ipv4Addr};
+
+fn build_response(
+    rcode: dnsmessage::RCode,
+    answers: &[dnsmessage::Resource<&str, &[u8]>],
+    authorities: &[dnsmessage::Resource<&str, &[u8]>],
+) -> Vec<u8> {
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(2308, rcode.into()))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap();
+    for answer in answers {
+        builder = builder.write_answer(answer).unwrap();
+    }
+
+    let mut builder = builder.finish_answers().unwrap();
+    for authority in authorities {
+        builder = builder.write_authority(authority).unwrap();
+    }
+
+    builder
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner()
+}
+
+#[test]
+fn test_min_ttl() {
+    let a = |ttl: u32, a: [u8; 4]| dnsmessage::Resource {
+        name: "www.example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl,
+        data: dnsmessage::ResourceData::A { a: Ipv4Addr::from(a) },
+    };
+    let ns = dnsmessage::Resource {
+        name: "example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl: 86400,
+        data: dnsmessage::ResourceData::NS { ns: "ns.example.org." },
+    };
+
+    let pkt = build_response(
+        dnsmessage::RCode::Success,
+        &[a(300, [192, 0, 2, 1]), a(60, [192, 0, 2, 2])],
+        &[ns],
+    );
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.min_ttl().unwrap(), Some(60));
+    assert_eq!(pkt.negative_ttl().unwrap(), None);
+
+    let pkt = build_response(dnsmessage::RCode::Success, &[], &[]);
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.min_ttl().unwrap(), None);
+}
+
+#[test]
+fn test_negative_ttl() {
+    let soa = dnsmessage::Resource {
+        name: "example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl: 3600,
+        data: dnsmessage::ResourceData::SOA {
+            ns: "ns.example.org.",
+            mbox: "hostmaster.example.org.",
+            serial: 2024010101,
+            refresh: 7200,
+            retry: 3600,
+            expire: 1209600,
+            min_ttl: 900,
+        },
+    };
+
+    let pkt = build_response(dnsmessage::RCode::NameError, &[], std::slice::from_ref(&soa));
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.negative_ttl().unwrap(), Some(900));
+    assert_eq!(pkt.min_ttl().unwrap(), Some(3600));
+
+    let pkt = build_response(dnsmessage::RCode::NameError, &[], &[dnsmessage::Resource { ttl: 300, ..soa }]);
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert_eq!(pkt.negative_ttl().unwrap(), Some(300));
+}
+
+#[test]
+fn test_set_all_ttls() {
+    let a = dnsmessage::Resource {
+        name: "www.example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl: 300,
+        data: dnsmessage::ResourceData::A {
+            a: Ipv4Addr::new(192, 0, 2, 1),
+        },
+    };
+    let ns = dnsmessage::Resource {
+        name: "example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl: 86400,
+        data: dnsmessage::ResourceData::NS { ns: "ns.example.org." },
+    };
+
+    let pkt = build_response(dnsmessage::RCode::Success, &[a.clone(), a], &[ns]);
+    let mut pkt = dnsmessage::Packet::new(pkt).unwrap();
+    pkt.set_all_ttls(30).unwrap();
+
+    let pkt = dnsmessage::Packet::new(pkt.into_inner()).unwrap();
+    for record in pkt.all_records() {
+        let (section, record) = record.unwrap();
+        if section == dnsmessage::Section::Additional {
+            assert!(matches!(record.data, dnsmessage::ResourceData::OPT { .. }));
+        } else {
+            assert_eq!(record.ttl, 30);
+        }
+    }
+    assert!(pkt.dnssec_ok().unwrap());
+}
+
+#[test]
+fn test_age_ttls() {
+    let a = |ttl: u32| dnsmessage::Resource {
+        name: "www.example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl,
+        data: dnsmessage::ResourceData::A {
+            a: Ipv4Addr::new(192, 0, 2, 1),
+        },
+    };
+
+    let pkt = build_response(dnsmessage::RCode::Success, &[a(300), a(60)], &[]);
+    let mut pkt = dnsmessage::Packet::new(pkt).unwrap();
+
+    pkt.age_ttls(45).unwrap();
+    assert_eq!(
+        pkt.answers().map(|answer| answer.unwrap().ttl).collect::<Vec<_>>(),
+        vec![255, 15]
+    );
+
+    pkt.age_ttls(100).unwrap();
+    assert_eq!(
+        pkt.answers().map(|answer| answer.unwrap().ttl).collect::<Vec<_>>(),
+        vec![155, 0]
+    );
+    assert_eq!(pkt.min_ttl().unwrap(), Some(0));
+    assert!(pkt.dnssec_ok().unwrap());
+}
+
+#[test]
+fn test_write_soa_authority() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::response(2308, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::AAAA.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .write_soa_authority(
+            "example.org.",
+            "ns.example.org.",
+            "hostmaster.example.org.",
+            2024010101,
+            7200,
+            3600,
+            1209600,
+            900,
+        )
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    assert!(pkt.answers().next().is_none());
+    assert_eq!(pkt.negative_ttl().unwrap(), Some(900));
+
+    let soa = pkt.authorities().next().unwrap().unwrap();
+    assert_eq!(TryInto::<String>::try_into(&soa.name).unwrap(), "example.org.");
+    assert_eq!(soa.class, dnsmessage::Class::INET.into());
+    assert_eq!(soa.ttl, 900);
+    assert_eq!(
+        soa.data.try_into_owned::<String, Vec<u8>>().unwrap(),
+        dnsmessage::ResourceData::SOA {
+            ns: "ns.example.org.".to_string(),
+            mbox: "hostmaster.example.org.".to_string(),
+            serial: 2024010101,
+            refresh: 7200,
+            retry: 3600,
+            expire: 1209600,
+            min_ttl: 900,
+        }
+    );
+}
+
+#[test]
+fn test_max_ttl() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .with_max_ttl(3600)
+        .write_header(dnsmessage::Header::response(2308, dnsmessage::RCode::Success.into()))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 1_000_000,
+            data: dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(192, 0, 2, 1),
+            },
+        })
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 60,
+            data: dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(192, 0, 2, 2),
+            },
+        })
+        .unwrap()
+        .write_raw_record(
+            "www.example.org.",
+            dnsmessage::Type::A.into(),
+            dnsmessage::Class::INET.into(),
+            1_000_000,
+            &[192, 0, 2, 3],
+        )
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .enable_dnssec(1232)
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let ttls = pkt.answers().map(|answer| answer.unwrap().ttl).collect::<Vec<_>>();
+    assert_eq!(ttls, vec![3600, 60, 3600]);
+    assert!(pkt.edns().unwrap().unwrap().dnssec_ok);
+}
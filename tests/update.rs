@@ -0,0 +1,182 @@
+use std::{io::Cursor, net::Ipv4Addr};
+
+#[test]
+fn test_update_view() {
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            opcode: dnsmessage::OPCODE_UPDATE,
+            ..dnsmessage::Header::query(2136)
+        })
+        .unwrap()
+        .write_question(&dnsmessage::Question {
+            name: "example.org.",
+            typ: dnsmessage::Type::SOA.into(),
+            class: dnsmessage::Class::INET.into(),
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_answer(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.example.org.",
+            class: dnsmessage::Class::ANY.into(),
+            ttl: 0,
+            data: dnsmessage::ResourceData::Unknown {
+                typ: dnsmessage::Type::CNAME.into(),
+                data: &[],
+            },
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .write_authority(&dnsmessage::Resource::<_, &[u8]> {
+            name: "www.example.org.",
+            class: dnsmessage::Class::INET.into(),
+            ttl: 300,
+            data: dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(192, 0, 2, 1),
+            },
+        })
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let view = dnsmessage::UpdateView::new(&pkt).unwrap();
+
+    let zone = view.zone().unwrap();
+    assert!(
+        zone.matches("example.org.", dnsmessage::Type::SOA.into(), dnsmessage::Class::INET.into())
+            .unwrap()
+    );
+
+    let prerequisites = view.prerequisites().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(prerequisites.len(), 1);
+    assert_eq!(prerequisites[0].class, dnsmessage::Class::ANY.into());
+
+    let updates = view.updates().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(
+        updates[0].data,
+        dnsmessage::ResourceData::A {
+            a: Ipv4Addr::new(192, 0, 2, 1)
+        }
+    );
+    assert!(view.additionals().next().is_none());
+
+    let query = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header::query(2136))
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+    let query = dnsmessage::Packet::new(query).unwrap();
+    assert!(matches!(
+        dnsmessage::UpdateView::new(&query),
+        Err(dnsmessage::Error::UnexpectedOpcode(0))
+    ));
+}
+
+#[test]
+fn test_build_update() {
+    let a = dnsmessage::Resource {
+        name: "www.example.org.",
+        class: dnsmessage::Class::INET.into(),
+        ttl: 300,
+        data: dnsmessage::ResourceData::<_, &[u8]>::A {
+            a: Ipv4Addr::new(192, 0, 2, 1),
+        },
+    };
+
+    let pkt = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            opcode: dnsmessage::OPCODE_UPDATE,
+            ..dnsmessage::Header::query(2136)
+        })
+        .unwrap()
+        .write_zone("example.org.")
+        .unwrap()
+        .finish_questions()
+        .unwrap()
+        .write_prerequisite(&dnsmessage::Prerequisite::<_, &[u8]>::RRsetNotExists {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::CNAME.into(),
+        })
+        .unwrap()
+        .finish_answers()
+        .unwrap()
+        .write_update(&dnsmessage::Update::<_, &[u8]>::DeleteRRset {
+            name: "www.example.org.",
+            typ: dnsmessage::Type::A.into(),
+        })
+        .unwrap()
+        .write_update(&dnsmessage::Update::Add(a.clone()))
+        .unwrap()
+        .write_update(&dnsmessage::Update::Delete(a.clone()))
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(&pkt[4..12], &[0, 1, 0, 1, 0, 3, 0, 0]);
+
+    let pkt = dnsmessage::Packet::new(&pkt[..]).unwrap();
+    let view = dnsmessage::UpdateView::new(&pkt).unwrap();
+    assert!(
+        view.zone()
+            .unwrap()
+            .matches("example.org.", dnsmessage::Type::SOA.into(), dnsmessage::Class::INET.into())
+            .unwrap()
+    );
+
+    let prerequisite = view.prerequisites().next().unwrap().unwrap();
+    assert_eq!(prerequisite.class, dnsmessage::Class::NONE.into());
+    assert_eq!(prerequisite.ttl, 0);
+    assert_eq!(prerequisite.data.typ(), dnsmessage::Type::CNAME.into());
+
+    let updates = view.updates().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        updates
+            .iter()
+            .map(|update| (update.class, update.ttl, update.data.typ()))
+            .collect::<Vec<_>>(),
+        vec![
+            (dnsmessage::Class::ANY.into(), 0, dnsmessage::Type::A.into()),
+            (dnsmessage::Class::INET.into(), 300, dnsmessage::Type::A.into()),
+            (dnsmessage::Class::NONE.into(), 0, dnsmessage::Type::A.into()),
+        ]
+    );
+    for update in &updates[1..] {
+        assert_eq!(
+            update.data,
+            dnsmessage::ResourceData::A {
+                a: Ipv4Addr::new(192, 0, 2, 1)
+            }
+        );
+    }
+}
+
+#[test]
+fn test_section_labels() {
+    assert_eq!(
+        dnsmessage::section_labels(0),
+        ["QUESTION", "ANSWER", "AUTHORITY", "ADDITIONAL"]
+    );
+    assert_eq!(
+        dnsmessage::section_labels(dnsmessage::OPCODE_UPDATE),
+        ["ZONE", "PREREQ", "UPDATE", "ADDITIONAL"]
+    );
+}
@@ -0,0 +1,128 @@
+use std::{io::Cursor, net::Ipv4Addr};
+
+fn build_answers(answers: &[dnsmessage::ResourceData<&str, &[u8]>]) -> dnsmessage::Packet<Vec<u8>> {
+    let mut builder = dnsmessage::Builder::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .write_header(dnsmessage::Header {
+            id: 5936,
+            resp: true,
+            opcode: 0,
+            rcode: dnsmessage::RCode::Success.into(),
+            flags: dnsmessage::HeaderFlags::AUTHORITATIVE,
+        })
+        .unwrap()
+        .finish_questions()
+        .unwrap();
+
+    for answer in answers {
+        builder = builder
+            .write_answer(&dnsmessage::Resource {
+                name: "example.org.",
+                class: dnsmessage::Class::INET.into(),
+                ttl: 3600,
+                data: answer.clone(),
+            })
+            .unwrap();
+    }
+
+    let pkt = builder
+        .finish_answers()
+        .unwrap()
+        .finish_authorities()
+        .unwrap()
+        .finish_additionals()
+        .unwrap()
+        .into_inner();
+
+    dnsmessage::Packet::new(pkt).unwrap()
+}
+
+#[test]
+fn test_axfr() {
+    let soa = dnsmessage::ResourceData::SOA {
+        ns: "ns.example.org.",
+        mbox: "hostmaster.example.org.",
+        serial: 2025010101,
+        refresh: 7200,
+        retry: 3600,
+        expire: 1209600,
+        min_ttl: 300,
+    };
+
+    let mut reader = dnsmessage::XfrReader::new();
+    reader
+        .push(build_answers(&[
+            soa.clone(),
+            dnsmessage::ResourceData::NS { ns: "ns.example.org." },
+            dnsmessage::ResourceData::A {
+                a: Ipv4Addr::from([192u8, 0, 2, 1]),
+            },
+        ]))
+        .unwrap();
+    assert!(!reader.is_complete());
+
+    reader
+        .push(build_answers(&[
+            dnsmessage::ResourceData::A {
+                a: Ipv4Addr::from([192u8, 0, 2, 2]),
+            },
+            soa.clone(),
+        ]))
+        .unwrap();
+    assert!(reader.is_complete());
+
+    let records = reader
+        .records()
+        .map(|record| record.unwrap().data.try_into_owned::<String, Vec<u8>>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), 5);
+    assert_eq!(records[0], soa.clone().try_into_owned().unwrap());
+    assert_eq!(records[4], soa.try_into_owned().unwrap());
+
+    assert!(matches!(
+        reader.push(build_answers(&[dnsmessage::ResourceData::NS { ns: "ns.example.org." }])),
+        Err(dnsmessage::Error::InvalidZoneTransfer)
+    ));
+
+    let mut reader = dnsmessage::XfrReader::new();
+    assert!(matches!(
+        reader.push(build_answers(&[dnsmessage::ResourceData::NS { ns: "ns.example.org." }])),
+        Err(dnsmessage::Error::InvalidZoneTransfer)
+    ));
+
+    // a second SOA with another serial doesn't close the transfer
+    let soa = |serial: u32| dnsmessage::ResourceData::SOA {
+        ns: "ns.example.org.",
+        mbox: "hostmaster.example.org.",
+        serial,
+        refresh: 7200,
+        retry: 3600,
+        expire: 1209600,
+        min_ttl: 300,
+    };
+
+    let mut reader = dnsmessage::XfrReader::new();
+    reader.push(build_answers(&[soa(2025010101)])).unwrap();
+    assert!(matches!(
+        reader.push(build_answers(&[soa(2025010102)])),
+        Err(dnsmessage::Error::InvalidZoneTransfer)
+    ));
+    assert!(!reader.is_complete());
+    reader.push(build_answers(&[soa(2025010101)])).unwrap();
+    assert!(reader.is_complete());
+}
+
+#[test]
+fn test_serial_newer() {
+    assert_eq!(dnsmessage::serial_newer(2, 1), Some(true));
+    assert_eq!(dnsmessage::serial_newer(1, 2), Some(false));
+    assert_eq!(dnsmessage::serial_newer(7, 7), Some(false));
+
+    assert_eq!(dnsmessage::serial_newer(0x0000_0001, 0xffff_ffff), Some(true));
+    assert_eq!(dnsmessage::serial_newer(0xffff_ffff, 0x0000_0001), Some(false));
+    assert_eq!(dnsmessage::serial_newer(0x7fff_ffff, 0), Some(true));
+    assert_eq!(dnsmessage::serial_newer(0x8000_0001, 0), Some(false));
+
+    assert_eq!(dnsmessage::serial_newer(0x8000_0000, 0), None);
+    assert_eq!(dnsmessage::serial_newer(0, 0x8000_0000), None);
+}